@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+use adventofcode2018::input::{self, Source};
+use adventofcode2018::lines;
+use adventofcode2018::machine::{Engine, Machine};
+
+// day19 part 1 runs the whole program, day21 stops at the first hits of the comparison
+// at ip 28, which is what its part 2 does thousands of times; breakpoints don't take the
+// compiled engine off its fast path
+const DAY21_HITS: usize = 50;
+
+fn day19(input: &[&str], engine: Engine) -> i32 {
+    let mut machine = Machine::<6>::parse(input).unwrap();
+    machine.set_engine(engine);
+    machine.run();
+    machine.registers.get(&0)
+}
+
+fn day21(input: &[&str], engine: Engine) -> i32 {
+    let mut machine = Machine::<6>::parse(input).unwrap();
+    machine.set_engine(engine);
+    machine.add_breakpoint(28);
+    for _ in 0..DAY21_HITS {
+        machine.resume();
+    }
+    machine.registers.get(&4)
+}
+
+fn timed(f: impl Fn() -> i32) -> (i32, Duration) {
+    let start = Instant::now();
+    let res = f();
+    (res, start.elapsed())
+}
+
+fn bench(name: &str, day: u8, f: fn(&[&str], Engine) -> i32) {
+    let input = match input::load(&Source::Day(day)) {
+        Ok(input) => input,
+        Err(e) => {
+            println!("{}: skipped, {}", name, e);
+            return;
+        }
+    };
+    let input = lines(&input);
+
+    let (expected, interpreted) = timed(|| f(&input, Engine::Interpreter));
+    let (res, compiled) = timed(|| f(&input, Engine::Compiled));
+    assert_eq!(expected, res, "{}: the engines disagree", name);
+
+    println!(
+        "{}: interpreter {:?}, compiled {:?}, speedup {:.2}x",
+        name,
+        interpreted,
+        compiled,
+        interpreted.as_secs_f64() / compiled.as_secs_f64()
+    );
+}
+
+fn main() {
+    bench("day19 part 1", 19, day19);
+    bench("day21 breakpoints", 21, day21);
+}
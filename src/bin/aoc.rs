@@ -0,0 +1,212 @@
+use std::env;
+use std::process;
+use std::time::{Duration, Instant};
+
+use adventofcode2018::days;
+use adventofcode2018::input::{self, Source};
+use adventofcode2018::SolveError;
+
+const USAGE: &str = "usage:
+    aoc run <day|all> [--part 1|2] [--input <path|->]
+    aoc list
+
+inputs are read from $AOC_INPUT_DIR/NN.txt, or input/NN.txt when unset";
+
+enum Target {
+    Day(u8),
+    All,
+}
+
+struct RunArgs {
+    target: Target,
+    part: Option<u8>,
+    input: Option<Source>,
+}
+
+fn parse_run_args(args: &[String]) -> Result<RunArgs, String> {
+    let mut args = args.iter();
+
+    let target = match args.next().map(String::as_str) {
+        Some("all") => Target::All,
+        Some(day) => Target::Day(
+            day.parse()
+                .map_err(|_| format!("invalid day \"{}\"", day))?,
+        ),
+        None => return Err(String::from("missing day")),
+    };
+
+    let mut part = None;
+    let mut input = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" | "-p" => {
+                let value = args.next().ok_or("missing value for --part")?;
+                match value.as_str() {
+                    "1" => part = Some(1),
+                    "2" => part = Some(2),
+                    _ => return Err(format!("invalid part \"{}\"", value)),
+                }
+            }
+            "--input" | "-i" => {
+                let value = args.next().ok_or("missing value for --input")?;
+                input = Some(Source::from_arg(value));
+            }
+            _ => return Err(format!("unexpected argument \"{}\"", arg)),
+        }
+    }
+
+    if let Target::All = target {
+        if input.is_some() {
+            return Err(String::from("--input cannot be used with \"all\""));
+        }
+    }
+
+    Ok(RunArgs {
+        target,
+        part,
+        input,
+    })
+}
+
+fn load_input(day: u8, source: &Option<Source>) -> Result<String, String> {
+    let res = match source {
+        Some(source) => input::load(source),
+        None => input::load(&Source::Day(day)),
+    };
+    res.map_err(|e| e.to_string())
+}
+
+fn timed<T, F: FnOnce() -> T>(f: F) -> (T, Duration) {
+    let start = Instant::now();
+    let res = f();
+    (res, start.elapsed())
+}
+
+fn run_day(day: u8, args: &RunArgs) -> Result<(), String> {
+    let solution = days::solution(day).ok_or_else(|| format!("day {} not implemented", day))?;
+    let input = load_input(day, &args.input)?;
+
+    let failed = |e: SolveError| match e {
+        SolveError::Parse(e) => format!("malformed input for day {}:\n{}", day, e),
+        SolveError::Unsolvable(reason) => format!("no answer for day {}: {}", day, reason),
+    };
+
+    if args.part != Some(2) {
+        let (res, elapsed) = timed(|| solution.first(&input));
+        println!("{}", res.map_err(failed)?);
+        println!("elapsed {:?}", elapsed);
+    }
+    if args.part != Some(1) {
+        let (res, elapsed) = timed(|| solution.second(&input));
+        println!("{}", res.map_err(failed)?);
+        println!("elapsed {:?}", elapsed);
+    }
+
+    Ok(())
+}
+
+fn cell(day: u8, answer: Result<String, SolveError>) -> String {
+    match answer {
+        Ok(answer) if answer.contains('\n') => String::from("(multi-line)"),
+        Ok(answer) => answer,
+        Err(SolveError::Parse(e)) => {
+            eprintln!("malformed input for day {}:\n{}", day, e);
+            String::from("(malformed input)")
+        }
+        Err(SolveError::Unsolvable(reason)) => {
+            eprintln!("no answer for day {}: {}", day, reason);
+            String::from("(no answer)")
+        }
+    }
+}
+
+fn run_all(args: &RunArgs) -> Result<(), String> {
+    let mut rows = Vec::new();
+    let mut total = Duration::default();
+
+    for day in days::available() {
+        let solution = days::solution(day).unwrap();
+        let input = match load_input(day, &None) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("skipping day {}: {}", day, e);
+                let missing = String::from("(no input)");
+                rows.push((day, missing.clone(), missing, Duration::default()));
+                continue;
+            }
+        };
+
+        let (first, first_elapsed) = match args.part {
+            Some(2) => (Ok(String::from("-")), Duration::default()),
+            _ => timed(|| solution.first(&input)),
+        };
+        let (second, second_elapsed) = match args.part {
+            Some(1) => (Ok(String::from("-")), Duration::default()),
+            _ => timed(|| solution.second(&input)),
+        };
+
+        let elapsed = first_elapsed + second_elapsed;
+        total += elapsed;
+        rows.push((day, cell(day, first), cell(day, second), elapsed));
+    }
+
+    let first_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(6);
+    let second_width = rows.iter().map(|r| r.2.len()).max().unwrap_or(0).max(6);
+
+    println!(
+        "day  {:<w1$}  {:<w2$}  elapsed",
+        "part 1",
+        "part 2",
+        w1 = first_width,
+        w2 = second_width
+    );
+    for (day, first, second, elapsed) in rows {
+        println!(
+            "{:>3}  {:<w1$}  {:<w2$}  {:?}",
+            day,
+            first,
+            second,
+            elapsed,
+            w1 = first_width,
+            w2 = second_width
+        );
+    }
+    println!("total {:?}", total);
+
+    Ok(())
+}
+
+fn with_usage(e: String) -> String {
+    format!("{}\n{}", e, USAGE)
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("run") => {
+            let args = parse_run_args(&args[1..]).map_err(with_usage)?;
+            match args.target {
+                Target::Day(day) => run_day(day, &args),
+                Target::All => run_all(&args),
+            }
+        }
+        Some("list") => {
+            days::available().for_each(|day| println!("{:02}", day));
+            Ok(())
+        }
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(cmd) => Err(with_usage(format!("unknown command \"{}\"", cmd))),
+        None => Err(with_usage(String::from("missing command"))),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,257 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+
+use adventofcode2018::input::{self, Source};
+use adventofcode2018::lines;
+use adventofcode2018::machine::cfg::Cfg;
+use adventofcode2018::machine::debugger::{StopReason, Watchpoint};
+use adventofcode2018::machine::decompiler::decompile;
+use adventofcode2018::machine::registry::Registry;
+use adventofcode2018::machine::snapshot::Snapshot;
+use adventofcode2018::machine::Machine;
+
+const USAGE: &str = "usage:
+    elfdbg <day|path> [--optimize]
+
+programs are assembled with the extended instructions: divr, divi, modr, modi, out, in
+and halt";
+
+const HELP: &str = "commands:
+    s, step [n]            execute n instructions (default 1)
+    c, continue [n]        run until a breakpoint, a watchpoint or the end of the program,
+                           executing at most n instructions
+    run                    run until the end of the program, ignoring breakpoints
+    b, break <ip>          add a breakpoint
+    d, delete <ip>         remove a breakpoint
+    w, watch <r> [value]   stop when register r changes, or when it takes the value
+    unwatch <r>            remove the watchpoints on register r
+    r, regs                show the registers
+    set <r> <value>        change a register
+    l, list [ip]           show the program around ip (default the current one)
+    info                   show breakpoints and watchpoints
+    trace <path|off>       write every executed instruction to a file, or stop doing it
+    profile                show per-instruction hit counts and the hottest loops
+    disasm                 show the whole program
+    decompile              show the program as C-like pseudo-code
+    cfg [path]             show the control-flow graph as Graphviz DOT, or write it to a file
+    save <path>            write a snapshot of the machine to a file
+    restore <path>         continue from a snapshot written by save
+    h, help                show this message
+    q, quit                exit";
+
+type Debuggee = Machine<6>;
+
+fn load(arg: &str, optimize: bool) -> Result<Debuggee, String> {
+    let source = match arg.parse() {
+        Ok(day) => Source::Day(day),
+        Err(_) => Source::from_arg(arg),
+    };
+    if source == Source::Stdin {
+        return Err(String::from("the program cannot be read from stdin"));
+    }
+
+    let input = input::load(&source).map_err(|e| e.to_string())?;
+    let mut machine = Machine::assemble_extended(&lines(&input), Registry::extended())
+        .map_err(|e| format!("malformed program {}:\n{}", source, e))?;
+    if optimize {
+        machine.optimize();
+    }
+    machine.enable_profiling();
+    Ok(machine)
+}
+
+fn show_registers(machine: &Debuggee) {
+    let regs = machine.registers.all();
+    let regs: Vec<String> = regs
+        .iter()
+        .enumerate()
+        .map(|(i, r)| format!("r{}={}", i, r))
+        .collect();
+    println!("ip={} {}", machine.curr_ip(), regs.join(" "));
+    if !machine.io.output.is_empty() {
+        let output: Vec<String> = machine.io.output.iter().map(|v| v.to_string()).collect();
+        println!("output: {}", output.join(" "));
+    }
+}
+
+fn list(machine: &Debuggee, ip: i32) {
+    let program = machine.program();
+    let breakpoints = machine.breakpoints();
+    for i in (ip - 3).max(0)..(ip + 4).min(program.len() as i32) {
+        let marker = if i == machine.curr_ip() { "=>" } else { "  " };
+        let bp = if breakpoints.contains(&i) { "*" } else { " " };
+        println!("{}{} {:>3}  {}", marker, bp, i, program[i as usize]);
+    }
+}
+
+fn arg<T: std::str::FromStr>(args: &[&str], i: usize, name: &str) -> Result<T, String> {
+    let value = args.get(i).ok_or_else(|| format!("missing {}", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} \"{}\"", name, value))
+}
+
+fn stopped(machine: &Debuggee, reason: StopReason) {
+    println!("{}", reason);
+    show_registers(machine);
+}
+
+// returns false when the session is over
+fn command(machine: &mut Debuggee, line: &str) -> Result<bool, String> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    match args.first().copied() {
+        None => {}
+        Some("s") | Some("step") => {
+            let n = match args.get(1) {
+                Some(_) => arg(&args, 1, "count")?,
+                None => 1,
+            };
+            let reason = machine.step(n);
+            stopped(machine, reason);
+        }
+        Some("c") | Some("continue") => {
+            let reason = match args.get(1) {
+                Some(_) => machine.run_for(arg(&args, 1, "count")?),
+                None => machine.resume(),
+            };
+            stopped(machine, reason);
+        }
+        Some("run") => {
+            machine.run();
+            let reason = machine.stopped().unwrap_or(StopReason::Halted);
+            stopped(machine, reason);
+        }
+        Some("b") | Some("break") => machine.add_breakpoint(arg(&args, 1, "ip")?),
+        Some("d") | Some("delete") => {
+            let ip = arg(&args, 1, "ip")?;
+            if !machine.remove_breakpoint(ip) {
+                return Err(format!("no breakpoint at {}", ip));
+            }
+        }
+        Some("w") | Some("watch") => {
+            let r = arg(&args, 1, "register")?;
+            let watchpoint = match args.get(2) {
+                Some(_) => Watchpoint::Value(r, arg(&args, 2, "value")?),
+                None => Watchpoint::Write(r),
+            };
+            machine.add_watchpoint(watchpoint);
+        }
+        Some("unwatch") => {
+            let r = arg(&args, 1, "register")?;
+            if !machine.remove_watchpoints(r) {
+                return Err(format!("no watchpoint on r{}", r));
+            }
+        }
+        Some("r") | Some("regs") => show_registers(machine),
+        Some("set") => {
+            let r = arg(&args, 1, "register")?;
+            let value = arg(&args, 2, "value")?;
+            machine.registers.set(&r, value);
+        }
+        Some("l") | Some("list") => {
+            let ip = match args.get(1) {
+                Some(_) => arg(&args, 1, "ip")?,
+                None => machine.curr_ip(),
+            };
+            list(machine, ip);
+        }
+        Some("info") => {
+            println!("breakpoints: {:?}", machine.breakpoints());
+            let watchpoints: Vec<String> = machine
+                .watchpoints()
+                .iter()
+                .map(|w| w.to_string())
+                .collect();
+            println!("watchpoints: [{}]", watchpoints.join(", "));
+        }
+        Some("trace") => match args.get(1).copied() {
+            Some("off") => machine.stop_trace().map_err(|e| e.to_string())?,
+            Some(path) => {
+                machine.stop_trace().map_err(|e| e.to_string())?;
+                let file =
+                    File::create(path).map_err(|e| format!("failed to create {}: {}", path, e))?;
+                machine.trace_to(BufWriter::new(file));
+            }
+            None => return Err(String::from("missing path")),
+        },
+        Some("profile") => {
+            if let Some(profile) = machine.profile() {
+                print!("{}", profile.report(machine.program()));
+            }
+        }
+        Some("disasm") => print!("{}", machine),
+        Some("decompile") => print!("{}", decompile(machine.program(), machine.ip_reg)),
+        Some("cfg") => {
+            let dot = Cfg::build(machine.program(), machine.ip_reg).to_dot(machine.program());
+            match args.get(1) {
+                Some(path) => {
+                    fs::write(path, dot).map_err(|e| format!("failed to write {}: {}", path, e))?
+                }
+                None => print!("{}", dot),
+            }
+        }
+        Some("save") => {
+            let path = args.get(1).ok_or("missing path")?;
+            let snapshot = machine.snapshot().to_string();
+            fs::write(path, snapshot).map_err(|e| format!("failed to write {}: {}", path, e))?
+        }
+        Some("restore") => {
+            let path = args.get(1).ok_or("missing path")?;
+            let text =
+                fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+            let snapshot = Snapshot::parse_extended(&lines(&text), machine.registry())
+                .map_err(|e| format!("malformed snapshot {}:\n{}", path, e))?;
+            machine.restore(&snapshot);
+            show_registers(machine);
+        }
+        Some("h") | Some("help") => println!("{}", HELP),
+        Some("q") | Some("quit") => {
+            machine.stop_trace().map_err(|e| e.to_string())?;
+            return Ok(false);
+        }
+        Some(cmd) => return Err(format!("unknown command \"{}\", try \"help\"", cmd)),
+    }
+    Ok(true)
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (path, optimize) = match args {
+        [path] => (path, false),
+        [path, flag] if flag == "--optimize" => (path, true),
+        _ => return Err(format!("unexpected arguments\n{}", USAGE)),
+    };
+    let mut machine = load(path, optimize)?;
+    show_registers(&machine);
+
+    let stdin = io::stdin();
+    loop {
+        print!("(elfdbg) ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+
+        let mut line = String::new();
+        if stdin
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| e.to_string())?
+            == 0
+        {
+            return machine.stop_trace().map_err(|e| e.to_string());
+        }
+        match command(&mut machine, &line) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
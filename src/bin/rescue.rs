@@ -0,0 +1,85 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use adventofcode2018::days::day22::Route;
+use adventofcode2018::input::{self, Source};
+
+const USAGE: &str = "usage:
+    rescue [path|-] [--minute <n>] [--ppm <path>] [--scale <n>]
+
+draws the fastest route of day 22 over the cave, as it is by the given minute if any;
+the input defaults to the one of day 22";
+
+struct Args {
+    source: Source,
+    minute: Option<u32>,
+    ppm: Option<String>,
+    scale: usize,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut res = Args {
+        source: Source::Day(22),
+        minute: None,
+        ppm: None,
+        scale: 4,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("missing value for {}", name));
+        match arg.as_str() {
+            "--minute" | "-m" => {
+                let value = value("--minute")?;
+                res.minute = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid minute \"{}\"", value))?,
+                );
+            }
+            "--ppm" => res.ppm = Some(value("--ppm")?.clone()),
+            "--scale" => {
+                let value = value("--scale")?;
+                res.scale = match value.parse() {
+                    Ok(scale) if scale > 0 => scale,
+                    _ => return Err(format!("invalid scale \"{}\"", value)),
+                };
+            }
+            path if !path.starts_with("--") || path == "-" => res.source = Source::from_arg(path),
+            _ => return Err(format!("unexpected argument \"{}\"", arg)),
+        }
+    }
+    Ok(res)
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args).map_err(|e| format!("{}\n{}", e, USAGE))?;
+    let input = input::load(&args.source).map_err(|e| e.to_string())?;
+    let route =
+        Route::parse(&input).map_err(|e| format!("malformed input {}:\n{}", args.source, e))?;
+
+    print!("{}", route.render(args.minute));
+    let states = &route.path.states;
+    let switches = states.windows(2).filter(|w| w[0].tool != w[1].tool).count();
+    println!(
+        "{} minutes, {} moves and {} tool switches",
+        route.minutes(),
+        states.len() - 1 - switches,
+        switches
+    );
+
+    if let Some(path) = args.ppm {
+        fs::write(&path, route.render_ppm(args.minute, args.scale))
+            .map_err(|e| format!("cannot write {}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
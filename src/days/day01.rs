@@ -0,0 +1,106 @@
+use crate::*;
+
+use std::collections::HashSet;
+
+fn first(input: &[&str]) -> Result<i32, ParseErrors> {
+    Ok(parse(input)?.iter().sum())
+}
+
+fn second(input: &[&str]) -> Result<i32, ParseErrors> {
+    let mut cur = 0;
+    let mut visited = HashSet::new();
+    visited.insert(cur);
+
+    let input = parse(input)?;
+
+    let mut find = || {
+        input
+            .iter()
+            .map(|x| {
+                cur += x;
+                cur
+            })
+            .find(|x| !visited.insert(*x))
+    };
+
+    loop {
+        if let Some(i) = find() {
+            return Ok(i);
+        }
+    }
+}
+
+fn parse(input: &[&str]) -> Result<Vec<i32>, ParseErrors> {
+    parse_lines_with(input, |line| parse_value(line, "frequency change"))
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        assert_eq!(Ok(3), first(&["+1", "-2", "+3", "+1"]));
+    }
+
+    #[test]
+    fn test11() {
+        assert_eq!(Ok(3), first(&["+1", "+1", "+1"]));
+    }
+
+    #[test]
+    fn test12() {
+        assert_eq!(Ok(0), first(&["+1", "+1", "-2"]));
+    }
+
+    #[test]
+    fn test13() {
+        assert_eq!(Ok(-6), first(&["-1", "-2", "-3"]));
+    }
+
+    #[test]
+    fn test2() {
+        assert_eq!(Ok(2), second(&["+1", "-2", "+3", "+1"]));
+    }
+
+    #[test]
+    fn test21() {
+        assert_eq!(Ok(0), second(&["+1", "-1"]));
+    }
+
+    #[test]
+    fn test22() {
+        assert_eq!(Ok(10), second(&["+3", "+3", "+4", "-2", "-4"]));
+    }
+
+    #[test]
+    fn test23() {
+        assert_eq!(Ok(5), second(&["-6", "+3", "+8", "+5", "-6"]));
+    }
+
+    #[test]
+    fn test24() {
+        assert_eq!(Ok(14), second(&["+7", "+7", "-2", "-7", "-4"]));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let errors = first(&["+1", "x", "+3", "-"]).err().unwrap();
+        assert_eq!(
+            vec![2, 4],
+            errors.0.iter().map(|e| e.line).collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use adventofcode2018::*;
+use crate::*;
 
 use itertools::Itertools;
 
@@ -18,7 +18,7 @@ fn first(input: &[&str]) -> u32 {
         (couples, triples)
     });
 
-    (couples * triples) as u32
+    couples * triples
 }
 
 fn second(input: &[&str]) -> String {
@@ -71,22 +71,21 @@ type Groups = (bool, bool);
 fn count(input: &str) -> Groups {
     let groups = input.chars().sorted().group_by(|&c| c);
     let counts: Vec<usize> = groups.into_iter().map(|(_, g)| g.count()).collect();
-    let couples = counts.iter().any(|&x| x == 2);
-    let triples = counts.iter().any(|&x| x == 3);
+    let couples = counts.contains(&2);
+    let triples = counts.contains(&3);
     (couples, triples)
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+pub struct Day02;
 
-    let input = read_file("02");
-    let input: Vec<&str> = input.trim().split('\n').collect();
-
-    println!("{}", first(&input));
-
-    println!("{}", second(&input));
+impl Solution for Day02 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input)).to_string())
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input)).to_string())
+    }
 }
 
 #[cfg(test)]
@@ -132,9 +131,7 @@ mod test {
     fn test() {
         assert_eq!(
             12,
-            first(&vec![
-                "abcdef", "bababc", "abbcde", "abcccd", "aabcdd", "abcdee", "ababab"
-            ])
+            first(&["abcdef", "bababc", "abbcde", "abcccd", "aabcdd", "abcdee", "ababab"])
         );
     }
 
@@ -142,9 +139,7 @@ mod test {
     fn test2() {
         assert_eq!(
             "fgij",
-            second(&vec![
-                "abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz"
-            ])
+            second(&["abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz"])
         )
     }
 }
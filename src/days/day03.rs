@@ -1,15 +1,12 @@
-use adventofcode2018::*;
+use crate::*;
 
 use std::collections::HashSet;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-fn first(input: &[&str]) -> u32 {
-    let claims = input.iter().map(|c| match Claim::new(c) {
-        Ok(o) => o,
-        Err(e) => panic!("failed to parse \"{}\": {}", c, e),
-    });
+fn first(input: &[&str]) -> Result<u32, ParseErrors> {
+    let claims: Vec<Claim> = parse_lines(input)?;
 
     let mut visited = HashSet::new();
     let mut doubles = HashSet::new();
@@ -25,17 +22,11 @@ fn first(input: &[&str]) -> u32 {
         }
     }
 
-    doubles.len() as u32
+    Ok(doubles.len() as u32)
 }
 
-fn second(input: &[&str]) -> u32 {
-    let claims: Vec<Claim> = input
-        .iter()
-        .map(|c| match Claim::new(c) {
-            Ok(o) => o,
-            Err(e) => panic!("failed to parse \"{}\": {}", c, e),
-        })
-        .collect();
+fn second(input: &[&str]) -> Result<u32, ParseErrors> {
+    let claims: Vec<Claim> = parse_lines(input)?;
 
     fn overlap(a: &Claim, b: &Claim) -> bool {
         fn o(ai: usize, af: usize, bi: usize, bf: usize) -> bool {
@@ -48,11 +39,12 @@ fn second(input: &[&str]) -> u32 {
         o(a.x, a.x + a.w, b.x, b.x + b.w) && o(a.y, a.y + a.h, b.y, b.y + b.h)
     }
 
-    claims
+    let id = claims
         .iter()
-        .find(|a| !claims.iter().any(|b| a.id != b.id && overlap(&a, &b)))
+        .find(|a| !claims.iter().any(|b| a.id != b.id && overlap(a, b)))
         .unwrap()
-        .id as u32
+        .id;
+    Ok(id as u32)
 }
 
 struct Claim {
@@ -67,9 +59,9 @@ lazy_static! {
     static ref RE: Regex = Regex::new(r"^#(\d+) @ (\d+),(\d+): (\d+)x(\d+)$").unwrap();
 }
 
-impl Claim {
-    fn new(from: &str) -> Result<Claim, String> {
-        match RE.captures(from) {
+impl FromLine for Claim {
+    fn from_line(line: &str) -> Result<Claim, ParseError> {
+        match RE.captures(line) {
             Some(cap) => {
                 let c = Claim {
                     id: parse_capture(&cap, 1, "id")?,
@@ -80,22 +72,21 @@ impl Claim {
                 };
                 Ok(c)
             }
-            None => Err(format!("failed to parse {}", from)),
+            None => Err(ParseError::no_match(line, &RE)),
         }
     }
 }
 
-fn main() {
-    let start = std::time::Instant::now();
-
-    let input = read_file("03");
-    let input: Vec<&str> = input.trim().split('\n').collect();
-
-    println!("{}", first(&input));
+pub struct Day03;
 
-    println!("{}", second(&input));
+impl Solution for Day03 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -105,16 +96,16 @@ mod test {
     #[test]
     fn test1() {
         assert_eq!(
-            4,
-            first(&vec!["#1 @ 1,3: 4x4", "#2 @ 3,1: 4x4", "#3 @ 5,5: 2x2"])
+            Ok(4),
+            first(&["#1 @ 1,3: 4x4", "#2 @ 3,1: 4x4", "#3 @ 5,5: 2x2"])
         );
     }
 
     #[test]
     fn test2() {
         assert_eq!(
-            3,
-            second(&vec!["#1 @ 1,3: 4x4", "#2 @ 3,1: 4x4", "#3 @ 5,5: 2x2"])
+            Ok(3),
+            second(&["#1 @ 1,3: 4x4", "#2 @ 3,1: 4x4", "#3 @ 5,5: 2x2"])
         );
     }
 }
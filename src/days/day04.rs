@@ -1,55 +1,60 @@
-use adventofcode2018::*;
+use crate::*;
 
 use std::collections::HashMap;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-fn first(input: &[&str]) -> u32 {
-    let map = parse(input);
+fn first(input: &[&str]) -> Result<u32, ParseErrors> {
+    let map = parse(input)?;
     let guard = map
         .iter()
         .max_by_key(|g| g.1.values().sum::<u32>())
         .unwrap();
     let max_min = guard.1.iter().max_by_key(|m| m.1).unwrap().0;
-    u32::from(*max_min) * guard.0
+    Ok(u32::from(*max_min) * guard.0)
 }
 
-fn second(input: &[&str]) -> u32 {
-    let map = parse(input);
+fn second(input: &[&str]) -> Result<u32, ParseErrors> {
+    let map = parse(input)?;
     let guard = map
         .iter()
         .max_by_key(|g| g.1.values().max().unwrap())
         .unwrap();
     let max_min = guard.1.iter().max_by_key(|m| m.1).unwrap().0;
-    u32::from(*max_min) * guard.0
+    Ok(u32::from(*max_min) * guard.0)
 }
 
 type Id = u32;
 type Minute = u8;
 
-fn parse(input: &[&str]) -> HashMap<Id, HashMap<Minute, u32>> {
-    let mut input = input.to_vec();
-    input.sort_unstable();
+enum Event {
+    Guard(Id),
+    Sleep(Minute),
+    Wake(Minute),
+}
+
+fn parse(input: &[&str]) -> Result<HashMap<Id, HashMap<Minute, u32>>, ParseErrors> {
+    let events = parse_lines_with(input, parse_event)?;
+    let mut events: Vec<(&str, Event)> = input.iter().cloned().zip(events).collect();
+    events.sort_unstable_by_key(|e| e.0);
 
     let mut r: HashMap<Id, HashMap<Minute, u32>> = HashMap::new();
 
     let mut id = None;
     let mut start_minute = None;
-    for line in input {
-        if let Some(i) = parse_guard(line) {
-            id = Some(i);
-        } else if let Some(minute) = parse_sleep(&line) {
-            start_minute = Some(minute);
-        } else if let Some(end_minute) = parse_wake(&line) {
-            r.entry(id.unwrap()).or_insert_with(HashMap::new);
-
-            let minutes = r.get_mut(&id.unwrap()).unwrap();
-            fill_minutes(start_minute.unwrap(), end_minute, minutes);
+    for (_, event) in events {
+        match event {
+            Event::Guard(i) => id = Some(i),
+            Event::Sleep(minute) => start_minute = Some(minute),
+            Event::Wake(end_minute) => {
+                let minutes = r.entry(id.unwrap()).or_default();
+                fill_minutes(start_minute.unwrap(), end_minute, minutes);
+            }
         }
     }
 
-    r
+    Ok(r)
 }
 
 lazy_static! {
@@ -58,28 +63,20 @@ lazy_static! {
     static ref WAKES_RE: Regex = Regex::new(r"^.*:(\d{2})\] wakes up$").unwrap();
 }
 
-fn parse_guard(line: &str) -> Option<Id> {
+fn parse_event(line: &str) -> Result<Event, ParseError> {
     if let Some(cap) = GUARD_RE.captures(line) {
-        let id = parse_capture(&cap, 1, "id").unwrap();
-        return Some(id);
-    }
-    None
-}
-
-fn parse_sleep(line: &str) -> Option<Minute> {
-    if let Some(cap) = SLEEPS_RE.captures(line) {
-        let minute = parse_capture(&cap, 1, "minute").unwrap();
-        return Some(minute);
-    }
-    None
-}
-
-fn parse_wake(line: &str) -> Option<Minute> {
-    if let Some(cap) = WAKES_RE.captures(line) {
-        let minute = parse_capture(&cap, 1, "minute").unwrap();
-        return Some(minute);
+        Ok(Event::Guard(parse_capture(&cap, 1, "id")?))
+    } else if let Some(cap) = SLEEPS_RE.captures(line) {
+        Ok(Event::Sleep(parse_capture(&cap, 1, "minute")?))
+    } else if let Some(cap) = WAKES_RE.captures(line) {
+        Ok(Event::Wake(parse_capture(&cap, 1, "minute")?))
+    } else {
+        Err(ParseError::new(
+            1,
+            line,
+            "a guard shift, falls asleep or wakes up record",
+        ))
     }
-    None
 }
 
 fn fill_minutes(start_minute: Minute, end_minute: Minute, minutes: &mut HashMap<Minute, u32>) {
@@ -88,17 +85,16 @@ fn fill_minutes(start_minute: Minute, end_minute: Minute, minutes: &mut HashMap<
     }
 }
 
-fn main() {
-    let start = std::time::Instant::now();
-
-    let input = read_file("04");
-    let input: Vec<&str> = input.trim().split('\n').collect();
+pub struct Day04;
 
-    println!("{}", first(&input));
-
-    println!("{}", second(&input));
+impl Solution for Day04 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -129,11 +125,11 @@ mod test {
 
     #[test]
     fn test1() {
-        assert_eq!(240, first(&data()));
+        assert_eq!(Ok(240), first(&data()));
     }
 
     #[test]
     fn test2() {
-        assert_eq!(4455, second(&data()));
+        assert_eq!(Ok(4455), second(&data()));
     }
 }
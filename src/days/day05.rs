@@ -1,4 +1,4 @@
-use adventofcode2018::*;
+use crate::*;
 
 fn first(input: &str) -> u32 {
     strip(input.chars().map(|c| c as u8).collect()).len() as u32
@@ -68,17 +68,16 @@ fn strip(input: Vec<u8>) -> Vec<u8> {
     res
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+pub struct Day05;
 
-    let input = read_file("05");
-    let input: &str = input.trim();
-
-    println!("{}", first(&input));
-
-    println!("{}", second(&input));
+impl Solution for Day05 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(input.trim()).to_string())
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(input.trim()).to_string())
+    }
 }
 
 #[cfg(test)]
@@ -1,26 +1,26 @@
-use adventofcode2018::*;
+use crate::*;
 
 use std::collections::HashMap;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-fn first(input: &[&str]) -> u32 {
-    let points = parse(input);
+fn first(input: &[&str]) -> Result<u32, ParseErrors> {
+    let points = parse(input)?;
     let limits = limits(&points);
 
     let regions = build_map(&points, limits);
     let regions = regions.values();
 
     let inner = regions.filter(|r| inner_region(r, limits));
-    inner.map(|r| r.len()).max().unwrap() as u32
+    Ok(inner.map(|r| r.len()).max().unwrap() as u32)
 }
 
-fn second(input: &[&str], max_distance: Option<i32>) -> u32 {
+fn second(input: &[&str], max_distance: Option<i32>) -> Result<u32, ParseErrors> {
     const DISTANCE: i32 = 10000;
     let max_distance = max_distance.unwrap_or(DISTANCE);
 
-    let points = parse(input);
+    let points = parse(input)?;
     let limits = limits(&points);
     let ((minx, miny), (maxx, maxy)) = limits;
 
@@ -37,7 +37,7 @@ fn second(input: &[&str], max_distance: Option<i32>) -> u32 {
         }
     }
 
-    safe_points
+    Ok(safe_points)
 }
 
 type Point = (i32, i32);
@@ -103,30 +103,25 @@ lazy_static! {
     static ref RE: Regex = Regex::new(r"^(\d+), (\d+)$").unwrap();
 }
 
-fn parse(input: &[&str]) -> Vec<Point> {
-    input
-        .iter()
-        .map(|input| {
-            let cap = RE.captures(input).unwrap();
-            (
-                parse_capture(&cap, 1, "line").unwrap(),
-                parse_capture(&cap, 2, "line").unwrap(),
-            )
-        })
-        .collect()
+fn parse(input: &[&str]) -> Result<Vec<Point>, ParseErrors> {
+    parse_lines_with(input, |line| {
+        let cap = RE
+            .captures(line)
+            .ok_or_else(|| ParseError::no_match(line, &RE))?;
+        Ok((parse_capture(&cap, 1, "x")?, parse_capture(&cap, 2, "y")?))
+    })
 }
 
-fn main() {
-    let start = std::time::Instant::now();
-
-    let input = read_file("06");
-    let input: Vec<&str> = input.trim().split('\n').collect();
-
-    println!("{}", first(&input));
+pub struct Day06;
 
-    println!("{}", second(&input, None));
+impl Solution for Day06 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input), None)?.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -136,19 +131,16 @@ mod test {
     #[test]
     fn test1() {
         assert_eq!(
-            17,
-            first(&vec!["1, 1", "1, 6", "8, 3", "3, 4", "5, 5", "8, 9"])
+            Ok(17),
+            first(&["1, 1", "1, 6", "8, 3", "3, 4", "5, 5", "8, 9"])
         );
     }
 
     #[test]
     fn test2() {
         assert_eq!(
-            16,
-            second(
-                &vec!["1, 1", "1, 6", "8, 3", "3, 4", "5, 5", "8, 9"],
-                Some(32)
-            )
+            Ok(16),
+            second(&["1, 1", "1, 6", "8, 3", "3, 4", "5, 5", "8, 9"], Some(32))
         );
     }
 }
@@ -1,24 +1,24 @@
-use adventofcode2018::*;
+use crate::*;
 
 use std::collections::{HashMap, HashSet};
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-fn first(input: &[&str]) -> String {
-    let mut deps = parse(input);
+fn first(input: &[&str]) -> Result<String, ParseErrors> {
+    let mut deps = parse(input)?;
 
-    travel(&mut deps)
+    Ok(travel(&mut deps))
 }
 
-fn second(input: &[&str]) -> usize {
+fn second(input: &[&str]) -> Result<usize, ParseErrors> {
     do_second(input, 5, 60)
 }
 
-fn do_second(input: &[&str], worker_num: usize, delay: usize) -> usize {
-    let mut deps = parse(input);
+fn do_second(input: &[&str], worker_num: usize, delay: usize) -> Result<usize, ParseErrors> {
+    let mut deps = parse(input)?;
 
-    run(&mut deps, worker_num, delay)
+    Ok(run(&mut deps, worker_num, delay))
 }
 
 fn run(input: &mut HashMap<char, HashSet<char>>, worker_num: usize, delay: usize) -> usize {
@@ -102,42 +102,36 @@ lazy_static! {
         Regex::new(r"^Step (.) must be finished before step (.) can begin.$").unwrap();
 }
 
-fn parse(input: &[&str]) -> HashMap<char, HashSet<char>> {
-    let mut map: HashMap<char, HashSet<char>> = HashMap::new();
-
-    for s in input {
-        if let Some(cap) = RE.captures(s) {
-            let from = parse_capture::<char>(&cap, 1, "to").unwrap();
-            let to = parse_capture::<char>(&cap, 2, "from").unwrap();
+fn parse(input: &[&str]) -> Result<HashMap<char, HashSet<char>>, ParseErrors> {
+    let steps = parse_lines_with(input, |line| {
+        let cap = RE
+            .captures(line)
+            .ok_or_else(|| ParseError::no_match(line, &RE))?;
+        let from = parse_capture::<char>(&cap, 1, "from")?;
+        let to = parse_capture::<char>(&cap, 2, "to")?;
+        Ok((from, to))
+    })?;
 
-            map.entry(from).or_insert_with(HashSet::new);
-            map.entry(to).or_insert_with(HashSet::new);
+    let mut map: HashMap<char, HashSet<char>> = HashMap::new();
 
-            match map.get_mut(&to) {
-                Some(set) => {
-                    set.insert(from);
-                }
-                None => {
-                    panic!("can't happen");
-                }
-            };
-        }
+    for (from, to) in steps {
+        map.entry(from).or_default();
+        map.entry(to).or_default().insert(from);
     }
 
-    map
+    Ok(map)
 }
 
-fn main() {
-    let start = std::time::Instant::now();
-
-    let input = read_file("07");
-    let input: Vec<&str> = input.trim().split('\n').collect();
-
-    println!("{}", first(&input));
+pub struct Day07;
 
-    println!("{}", second(&input));
+impl Solution for Day07 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?)
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -147,8 +141,8 @@ mod test {
     #[test]
     fn test1() {
         assert_eq!(
-            "CABDFE",
-            first(&vec![
+            Ok(String::from("CABDFE")),
+            first(&[
                 "Step C must be finished before step A can begin.",
                 "Step C must be finished before step F can begin.",
                 "Step A must be finished before step B can begin.",
@@ -163,9 +157,9 @@ mod test {
     #[test]
     fn test2() {
         assert_eq!(
-            15,
+            Ok(15),
             do_second(
-                &vec![
+                &[
                     "Step C must be finished before step A can begin.",
                     "Step C must be finished before step F can begin.",
                     "Step A must be finished before step B can begin.",
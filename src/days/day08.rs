@@ -1,4 +1,4 @@
-use adventofcode2018::*;
+use crate::*;
 
 use std::slice::Iter;
 
@@ -25,7 +25,7 @@ fn second(input: &[usize]) -> usize {
             entry
                 .metadata
                 .iter()
-                .map(|i| entry.children.get(*i - 1).map(|c| value(c)).unwrap_or(0))
+                .map(|i| entry.children.get(*i - 1).map(value).unwrap_or(0))
                 .sum()
         }
     }
@@ -59,21 +59,36 @@ fn build_tree(input: &[usize]) -> Entry {
     build_entry(&mut input.iter())
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+fn parse(input: &str) -> Result<Vec<usize>, ParseErrors> {
+    let mut res = Vec::new();
+    let mut errors = Vec::new();
 
-    let input = read_file("08");
-    let input: Vec<usize> = input
-        .trim()
-        .split(' ')
-        .map(|s| s.parse().unwrap())
-        .collect();
+    let mut column = 1;
+    for s in input.trim().split(' ') {
+        match parse_value(s, "number") {
+            Ok(n) => res.push(n),
+            Err(e) => errors.push(e.at_line(1).at_column(column)),
+        }
+        column += s.len() + 1;
+    }
 
-    println!("{}", first(&input));
+    if errors.is_empty() {
+        Ok(res)
+    } else {
+        Err(ParseErrors(errors))
+    }
+}
 
-    println!("{}", second(&input));
+pub struct Day08;
 
-    println!("elapsed {:?}", start.elapsed());
+impl Solution for Day08 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&parse(input)?).to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&parse(input)?).to_string())
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use adventofcode2018::*;
+use crate::*;
 
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -106,21 +106,31 @@ lazy_static! {
         Regex::new(r"^(\d+) players; last marble is worth (\d+) points$").unwrap();
 }
 
-fn main() {
-    let start = std::time::Instant::now();
-
-    let input = read_file("09");
+fn parse(input: &str) -> Result<(usize, usize), ParseError> {
     let input = input.trim();
-    if let Some(cap) = RE.captures(&input) {
-        let players = parse_capture::<usize>(&cap, 1, "players").unwrap();
-        let max_value = parse_capture::<usize>(&cap, 2, "max_value").unwrap();
+    let parse_line = || {
+        let cap = RE
+            .captures(input)
+            .ok_or_else(|| ParseError::no_match(input, &RE))?;
+        let players = parse_capture::<usize>(&cap, 1, "players")?;
+        let max_value = parse_capture::<usize>(&cap, 2, "max_value")?;
+        Ok((players, max_value))
+    };
+    parse_line().map_err(|e: ParseError| e.at_line(1))
+}
 
-        println!("{}", first(players, max_value));
+pub struct Day09;
 
-        println!("{}", second(players, max_value));
+impl Solution for Day09 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        let (players, max_value) = parse(input)?;
+        Ok(first(players, max_value).to_string())
     }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        let (players, max_value) = parse(input)?;
+        Ok(second(players, max_value).to_string())
+    }
 }
 
 #[cfg(test)]
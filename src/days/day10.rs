@@ -1,10 +1,10 @@
-use adventofcode2018::*;
+use crate::*;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-fn first(input: &[&str]) {
-    let points = parse(input);
+fn first(input: &[&str]) -> Result<String, ParseErrors> {
+    let points: Vec<Point> = parse_lines(input)?;
 
     let candidate = (0..50000)
         .map(|t| {
@@ -19,11 +19,11 @@ fn first(input: &[&str]) {
         .unwrap();
 
     let (conf, (min, max)) = candidate;
-    print(&conf, min, max);
+    Ok(render(&conf, min, max))
 }
 
-fn second(input: &[&str]) {
-    let points = parse(input);
+fn second(input: &[&str]) -> Result<usize, ParseErrors> {
+    let points: Vec<Point> = parse_lines(input)?;
 
     let candidate = (0..50000)
         .map(|t| {
@@ -38,7 +38,7 @@ fn second(input: &[&str]) {
         .unwrap();
 
     let (_, _, t) = candidate;
-    println!(">>>> TIME={}", t);
+    Ok(t)
 }
 
 struct Coord {
@@ -72,13 +72,11 @@ fn min_max(conf: &[Coord]) -> (Coord, Coord) {
         .unwrap()
 }
 
-fn print(conf: &[Coord], min: Coord, max: Coord) {
+fn render(conf: &[Coord], min: Coord, max: Coord) -> String {
     let width = (max.x - min.x) as usize + 3;
     let height = (max.y - min.y) as usize + 3;
     let separator = "-".repeat(width);
 
-    println!("{}", separator);
-
     let mut screen = vec![vec!['.'; width]; height];
 
     conf.iter().for_each(|c| {
@@ -87,14 +85,15 @@ fn print(conf: &[Coord], min: Coord, max: Coord) {
         screen[rownum][colnum] = '#';
     });
 
+    let mut res = String::new();
+    res.push_str(&separator);
+    res.push('\n');
     for row in screen {
-        for ch in row {
-            print!("{}", ch);
-        }
-        println!();
+        res.extend(row);
+        res.push('\n');
     }
-
-    println!("{}", separator);
+    res.push_str(&separator);
+    res
 }
 
 struct Point {
@@ -122,30 +121,26 @@ fn conf_at_t(points: &[Point], t: usize) -> Vec<Coord> {
         .collect::<Vec<Coord>>()
 }
 
-fn parse(input: &[&str]) -> Vec<Point> {
-    input
-        .iter()
-        .map(|s| {
-            RE.captures(s)
-                .map(|c| {
-                    let startx = parse_capture::<isize>(&c, 1, "startx").unwrap();
-                    let starty = parse_capture::<isize>(&c, 2, "starty").unwrap();
-                    let speedx = parse_capture::<isize>(&c, 3, "speedx").unwrap();
-                    let speedy = parse_capture::<isize>(&c, 4, "speedy").unwrap();
-                    let start_coord = Coord {
-                        x: startx,
-                        y: starty,
-                    };
-
-                    Point {
-                        start_coord,
-                        speedx,
-                        speedy,
-                    }
-                })
-                .unwrap_or_else(|| panic!("failed to parse {}", s))
+impl FromLine for Point {
+    fn from_line(line: &str) -> Result<Point, ParseError> {
+        let c = RE
+            .captures(line)
+            .ok_or_else(|| ParseError::no_match(line, &RE))?;
+        let startx = parse_capture::<isize>(&c, 1, "startx")?;
+        let starty = parse_capture::<isize>(&c, 2, "starty")?;
+        let speedx = parse_capture::<isize>(&c, 3, "speedx")?;
+        let speedy = parse_capture::<isize>(&c, 4, "speedy")?;
+        let start_coord = Coord {
+            x: startx,
+            y: starty,
+        };
+
+        Ok(Point {
+            start_coord,
+            speedx,
+            speedy,
         })
-        .collect()
+    }
 }
 
 lazy_static! {
@@ -153,17 +148,16 @@ lazy_static! {
         Regex::new(r"^position=< ?(-?\d+),  ?(-?\d+)> velocity=< ?(-?\d+),  ?(-?\d+)>$").unwrap();
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+pub struct Day10;
 
-    let input = read_file("10");
-    let input: Vec<&str> = input.trim().split('\n').collect();
-
-    first(&input);
-
-    second(&input);
+impl Solution for Day10 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?)
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -172,7 +166,7 @@ mod test {
 
     #[test]
     fn test1() {
-        first(&vec![
+        let res = first(&[
             "position=< 9,  1> velocity=< 0,  2>",
             "position=< 7,  0> velocity=<-1,  0>",
             "position=< 3, -2> velocity=<-1,  1>",
@@ -205,5 +199,6 @@ mod test {
             "position=<14,  7> velocity=<-2,  0>",
             "position=<-3,  6> velocity=< 2, -1>",
         ]);
+        assert!(res.unwrap().contains(".#...#..###."));
     }
 }
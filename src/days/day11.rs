@@ -1,4 +1,4 @@
-use adventofcode2018::*;
+use crate::*;
 
 fn first(input: usize) -> (usize, usize) {
     let cells = build_cells(input);
@@ -83,15 +83,22 @@ fn power(x: usize, y: usize, serial: usize) -> isize {
     power as isize - 5
 }
 
-fn main() {
-    let start = std::time::Instant::now();
-    let input = read_file("11").trim().parse::<usize>().unwrap();
+fn parse(input: &str) -> Result<usize, ParseError> {
+    parse_value(input.trim(), "grid serial number").map_err(|e| e.at_line(1))
+}
 
-    println!("{:?}", first(input));
+pub struct Day11;
 
-    println!("{:?}", second(input));
+impl Solution for Day11 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        let (x, y) = first(parse(input)?);
+        Ok(format!("{},{}", x, y))
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        let (x, y, s) = second(parse(input)?);
+        Ok(format!("{},{},{}", x, y, s))
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,10 @@
-use adventofcode2018::*;
+use crate::*;
 
 use std::collections::HashMap;
 
+use lazy_static::lazy_static;
+use regex::Regex;
+
 fn first(initial: &str, rules: &HashMap<&str, char>) -> isize {
     let mut current = String::from(initial);
     let mut start_idx = 0;
@@ -89,26 +92,58 @@ fn next_state(cur: &str, rules: &HashMap<&str, char>) -> String {
     new
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+lazy_static! {
+    static ref INITIAL_RE: Regex = Regex::new(r"^initial state: ([.#]+)$").unwrap();
+    static ref RULE_RE: Regex = Regex::new(r"^([.#]{5}) => ([.#])$").unwrap();
+}
+
+type Rules<'a> = HashMap<&'a str, char>;
 
-    let input = read_file("12");
-    let input: Vec<&str> = input.trim().split('\n').collect();
+fn parse<'a>(input: &[&'a str]) -> Result<(&'a str, Rules<'a>), ParseErrors> {
+    let mut errors = Vec::new();
+
+    let first_line = input.first().cloned().unwrap_or("");
+    let initial = match INITIAL_RE.captures(first_line) {
+        Some(cap) => cap.get(1).unwrap().as_str(),
+        None => {
+            errors.push(ParseError::no_match(first_line, &INITIAL_RE).at_line(1));
+            ""
+        }
+    };
 
-    let initial = &input[0][15..];
     let mut rules = HashMap::new();
-    input[2..].iter().for_each(|&s| {
-        let mut s = s.split(" => ");
-        let rule = s.next().unwrap();
-        let res = s.next().unwrap().chars().next().unwrap();
-        rules.insert(rule, res);
-    });
+    for (i, &line) in input.iter().enumerate().skip(2) {
+        match RULE_RE.captures(line) {
+            Some(cap) => {
+                let rule = cap.get(1).unwrap().as_str();
+                let res = cap[2].chars().next().unwrap();
+                rules.insert(rule, res);
+            }
+            None => errors.push(ParseError::no_match(line, &RULE_RE).at_line(i + 1)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok((initial, rules))
+    } else {
+        Err(ParseErrors(errors))
+    }
+}
 
-    println!("{}", first(initial, &rules));
+pub struct Day12;
 
-    println!("{}", second(initial, &rules));
+impl Solution for Day12 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        let input = lines(input);
+        let (initial, rules) = parse(&input)?;
+        Ok(first(initial, &rules).to_string())
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        let input = lines(input);
+        let (initial, rules) = parse(&input)?;
+        Ok(second(initial, &rules).to_string())
+    }
 }
 
 #[cfg(test)]
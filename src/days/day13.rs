@@ -1,4 +1,4 @@
-use adventofcode2018::*;
+use crate::*;
 
 fn first(input: &[&str]) -> (usize, usize) {
     let board = get_board(input);
@@ -191,17 +191,18 @@ fn get_carts(input: &[&str]) -> Vec<Cart> {
         .collect()
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+pub struct Day13;
 
-    let input = read_file("13");
-    let input: Vec<&str> = input.trim_end().split('\n').collect();
-
-    println!("{:?}", first(&input));
-
-    println!("{:?}", second(&input));
+impl Solution for Day13 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        let (x, y) = first(&lines(input));
+        Ok(format!("{},{}", x, y))
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        let (x, y) = second(&lines(input));
+        Ok(format!("{},{}", x, y))
+    }
 }
 
 #[cfg(test)]
@@ -212,7 +213,7 @@ mod test {
     fn test() {
         assert_eq!(
             (7, 3),
-            first(&vec![
+            first(&[
                 "/->-\\        ",
                 "|   |  /----\\",
                 "| /-+--+-\\  |",
@@ -1,4 +1,4 @@
-use adventofcode2018::*;
+use crate::*;
 use itertools::Itertools;
 
 fn first(input: usize) -> String {
@@ -46,17 +46,17 @@ fn next(recipes: &mut Vec<u8>, indices: (usize, usize)) -> (usize, usize) {
     (next_idx(indices.0), next_idx(indices.1))
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+pub struct Day14;
 
-    let input = read_file("14");
-    let input = input.trim();
-
-    println!("{}", first(input.parse::<usize>().unwrap()));
-
-    println!("{}", second(input));
+impl Solution for Day14 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        let input = parse_value(input.trim(), "number of recipes").map_err(|e| e.at_line(1))?;
+        Ok(first(input))
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(input.trim()).to_string())
+    }
 }
 
 #[cfg(test)]
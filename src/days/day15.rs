@@ -0,0 +1,603 @@
+use crate::grid::{Point, SparseGrid};
+use crate::search::{bfs_until, Space};
+use crate::*;
+
+use itertools::Itertools;
+use std::cmp::Ordering;
+
+fn first(input: &[&str]) -> Result<usize, ParseErrors> {
+    let mut board = parse(input)?;
+    let turn = run_game(&mut board);
+    Ok((get_hp(&board, &Kind::Goblin) + get_hp(&board, &Kind::Elf)) * turn)
+}
+
+fn second(input: &[&str]) -> Result<usize, ParseErrors> {
+    let mut power = 3usize;
+    let mut max_fail = power;
+    let mut min_success = usize::MAX;
+    let mut outcome = 0;
+
+    let count = |board: &Board| {
+        board
+            .iter()
+            .filter(|(_, t)| matches!(t, Tile::Npc(Kind::Elf, _, _)))
+            .count()
+    };
+    loop {
+        let mut board = parse_powerelf(input, power)?;
+        let n_elves = count(&board);
+
+        let turn = run_game(&mut board);
+        let success = count(&board) == n_elves;
+
+        if success {
+            min_success = power;
+            outcome = (get_hp(&board, &Kind::Goblin) + get_hp(&board, &Kind::Elf)) * turn;
+        } else {
+            max_fail = power;
+        }
+
+        if min_success == max_fail + 1 {
+            break;
+        }
+
+        power = max_fail + (min_success - max_fail) / 2;
+    }
+
+    Ok(outcome)
+}
+
+fn run_game(board: &mut Board) -> usize {
+    let mut turn = 0;
+    while run_turn(board) {
+        turn += 1;
+    }
+    turn
+}
+
+type Path = Vec<Point>;
+
+#[derive(Copy, Clone)]
+enum Tile {
+    Wall,
+    Npc(Kind, usize, usize),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Kind {
+    Elf,
+    Goblin,
+}
+
+type Board = SparseGrid<Tile>;
+
+fn parse(input: &[&str]) -> Result<Board, ParseErrors> {
+    parse_powerelf(input, 3)
+}
+
+fn parse_powerelf(input: &[&str], elf_power: usize) -> Result<Board, ParseErrors> {
+    SparseGrid::parse_with(input, |c| match c {
+        '#' => Ok(Some(Tile::Wall)),
+        'E' => Ok(Some(Tile::Npc(Kind::Elf, elf_power, 200))),
+        'G' => Ok(Some(Tile::Npc(Kind::Goblin, 3, 200))),
+        '.' => Ok(None),
+        c => Err(ParseError::new(
+            1,
+            &c.to_string(),
+            "one of '#', '.', 'E', 'G'",
+        )),
+    })
+}
+
+// the open squares
+struct Cavern<'a>(&'a Board);
+
+impl<'a> Space for Cavern<'a> {
+    type State = Point;
+
+    // reading order, so that the first shortest path found is the one to take
+    fn neighbours(&self, p: &Point) -> Vec<(Point, u32)> {
+        p.neighbours4()
+            .iter()
+            .filter(|n| self.0.get(n).is_none())
+            .map(|n| (*n, 1))
+            .collect()
+    }
+}
+
+fn target_path(board: &Board, player_coord: &Point) -> Option<Path> {
+    let player_kind = get_kind(board, player_coord)?;
+
+    if adjacent(board, player_coord, &player_kind) {
+        return Some(Vec::new());
+    }
+
+    // the nearest square in range of an enemy, the first in reading order on ties
+    let in_range = |p: &Point| adjacent(board, p, &player_kind);
+    let reached = bfs_until(&Cavern(board), *player_coord, in_range);
+    let (target, _) = reached
+        .iter()
+        .filter(|(p, _)| in_range(p))
+        .min_by_key(|&(p, moves)| (moves, *p))?;
+    let path = reached.path_to(target)?;
+    Some(path.states.into_iter().skip(1).collect())
+}
+
+fn get_kind(board: &Board, player_coord: &Point) -> Option<Kind> {
+    board.get(player_coord).and_then(|t| match t {
+        Tile::Npc(k, _, _) => Some(*k),
+        _ => None,
+    })
+}
+
+fn adjacent(board: &Board, from: &Point, player_kind: &Kind) -> bool {
+    from.neighbours4()
+        .iter()
+        .any(|n| is_target(board, n, player_kind))
+}
+
+fn is_target(board: &Board, tgt: &Point, player_kind: &Kind) -> bool {
+    board
+        .get(tgt)
+        .filter(|&t| matches!(t, Tile::Npc(k, _, _) if *k != *player_kind))
+        .is_some()
+}
+
+fn mov(board: &mut Board, player: &Point) -> Option<Point> {
+    if !board.contains(player) {
+        return None;
+    }
+
+    let mut player = player;
+
+    if let Some(path) = target_path(board, player) {
+        if let Some(next_pos) = path.first() {
+            let p = board.remove(player).unwrap();
+            board.insert(*next_pos, p);
+            player = next_pos;
+        }
+
+        let power = if let Some(Tile::Npc(_, power, _)) = board.get(player) {
+            *power
+        } else {
+            0
+        };
+
+        if let Some(target_coord) = maybe_attack(board, player) {
+            if let Some(Tile::Npc(_, _, hp)) = board.get_mut(&target_coord) {
+                if *hp > power {
+                    *hp -= power;
+                } else {
+                    board.remove(&target_coord);
+                    return Some(target_coord);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn maybe_attack(board: &Board, player_coord: &Point) -> Option<Point> {
+    let player = board.get(player_coord);
+    if let Some(Tile::Npc(player_kind, _, _)) = player {
+        get_target(board, player_coord, player_kind)
+    } else {
+        None
+    }
+}
+
+fn get_target(board: &Board, player_coord: &Point, player_kind: &Kind) -> Option<Point> {
+    player_coord
+        .neighbours4()
+        .iter()
+        .filter(|&n| is_target(board, n, player_kind))
+        .copied()
+        .sorted_by(|t1, t2| {
+            if let (Some(Tile::Npc(_, _, hp1)), Some(Tile::Npc(_, _, hp2))) =
+                (board.get(t1), board.get(t2))
+            {
+                hp1.cmp(hp2)
+            } else {
+                Ordering::Equal
+            }
+        })
+        .next()
+}
+
+fn run_turn(board: &mut Board) -> bool {
+    let players = get_players(board);
+    let mut killed = Vec::new();
+
+    for player in players.iter() {
+        if killed.contains(player) {
+            continue;
+        }
+
+        if !board
+            .iter()
+            .any(|(_, t)| matches!(t, Tile::Npc(Kind::Elf, _, _)))
+            || !board
+                .iter()
+                .any(|(_, t)| matches!(t, Tile::Npc(Kind::Goblin, _, _)))
+        {
+            return false;
+        }
+
+        if let Some(pos) = mov(board, player) {
+            killed.push(pos);
+        }
+    }
+
+    true
+}
+
+fn get_players(board: &Board) -> Vec<Point> {
+    board
+        .iter()
+        .filter(|(_, t)| matches!(t, Tile::Npc(_, _, _)))
+        .map(|(c, _)| *c)
+        .sorted()
+        .collect()
+}
+
+fn get_hp(board: &Board, kind: &Kind) -> usize {
+    board
+        .values()
+        .map(|t| match t {
+            Tile::Npc(k, _, hp) if k == kind => hp,
+            _ => &0usize,
+        })
+        .sum()
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let board = parse(&["#E.", "..G"]).unwrap();
+        assert_eq!(3, board.len());
+        assert_eq!(
+            "line 2, column 3: expected one of '#', '.', 'E', 'G', found \"x\"",
+            parse(&["#E.", "..x"]).err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_path() {
+        let board = vec!["#######", "#E..G.#", "#...#.#", "#.G.#G#", "#######"];
+        let board = parse(&board).unwrap();
+
+        assert_eq!(
+            Some(vec![Point::new(2, 1), Point::new(3, 1)]),
+            target_path(&board, &Point::new(1, 1))
+        );
+    }
+
+    #[test]
+    fn test_path2() {
+        let board = vec!["#######", "#.E...#", "#.....#", "#...G.#", "#######"];
+        let board = parse(&board).unwrap();
+
+        assert_eq!(
+            Some(vec![Point::new(3, 1), Point::new(4, 1), Point::new(4, 2)]),
+            target_path(&board, &Point::new(2, 1))
+        );
+    }
+
+    #[test]
+    fn test_moves() {
+        let board = vec![
+            "#########",
+            "#G..G..G#",
+            "#.......#",
+            "#.......#",
+            "#G..E..G#",
+            "#.......#",
+            "#.......#",
+            "#G..G..G#",
+            "#########",
+        ];
+        let mut board = parse(&board).unwrap();
+
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(2, 1),
+                Point::new(6, 1),
+                Point::new(4, 2),
+                Point::new(4, 3),
+                Point::new(7, 3),
+                Point::new(2, 4),
+                Point::new(1, 6),
+                Point::new(4, 6),
+                Point::new(7, 6)
+            ],
+            get_players(&board)
+        );
+
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(3, 1),
+                Point::new(5, 1),
+                Point::new(4, 2),
+                Point::new(2, 3),
+                Point::new(4, 3),
+                Point::new(6, 3),
+                Point::new(1, 5),
+                Point::new(4, 5),
+                Point::new(7, 5)
+            ],
+            get_players(&board)
+        );
+
+        run_turn(&mut board);
+        assert_eq!(
+            vec![
+                Point::new(3, 2),
+                Point::new(4, 2),
+                Point::new(5, 2),
+                Point::new(3, 3),
+                Point::new(4, 3),
+                Point::new(5, 3),
+                Point::new(1, 4),
+                Point::new(4, 4),
+                Point::new(7, 5)
+            ],
+            get_players(&board)
+        );
+    }
+
+    #[test]
+    fn test_turns() {
+        let board = vec![
+            "#######", "#.G...#", "#...EG#", "#.#.#G#", "#..G#E#", "#.....#", "#######",
+        ];
+        let mut board = parse(&board).unwrap();
+
+        println!("TURN 1");
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(3, 1),
+                Point::new(4, 2),
+                Point::new(5, 2),
+                Point::new(3, 3),
+                Point::new(5, 3),
+                Point::new(5, 4),
+            ],
+            get_players(&board)
+        );
+
+        println!("TURN 2");
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(4, 1),
+                Point::new(3, 2),
+                Point::new(4, 2),
+                Point::new(5, 2),
+                Point::new(5, 3),
+                Point::new(5, 4),
+            ],
+            get_players(&board)
+        );
+
+        for i in 3..=23 {
+            println!("TURN {}", i);
+            assert!(run_turn(&mut board));
+        }
+        assert_eq!(
+            vec![
+                Point::new(4, 1),
+                Point::new(3, 2),
+                Point::new(5, 2),
+                Point::new(5, 3),
+                Point::new(5, 4),
+            ],
+            get_players(&board)
+        );
+
+        println!("TURN 24");
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(3, 1),
+                Point::new(4, 2),
+                Point::new(3, 3),
+                Point::new(5, 3),
+                Point::new(5, 4),
+            ],
+            get_players(&board)
+        );
+
+        println!("TURN 25");
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(2, 1),
+                Point::new(3, 2),
+                Point::new(5, 3),
+                Point::new(3, 4),
+                Point::new(5, 4),
+            ],
+            get_players(&board)
+        );
+
+        println!("TURN 26");
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(1, 1),
+                Point::new(2, 2),
+                Point::new(5, 3),
+                Point::new(5, 4),
+                Point::new(3, 5),
+            ],
+            get_players(&board)
+        );
+
+        println!("TURN 27");
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(1, 1),
+                Point::new(2, 2),
+                Point::new(5, 3),
+                Point::new(5, 4),
+                Point::new(4, 5),
+            ],
+            get_players(&board)
+        );
+
+        println!("TURN 28");
+        assert!(run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(1, 1),
+                Point::new(2, 2),
+                Point::new(5, 3),
+                Point::new(5, 4),
+                Point::new(5, 5),
+            ],
+            get_players(&board)
+        );
+
+        for i in 29..=47 {
+            println!("TURN {}", i);
+            assert!(run_turn(&mut board));
+        }
+        assert_eq!(
+            vec![
+                Point::new(1, 1),
+                Point::new(2, 2),
+                Point::new(5, 3),
+                Point::new(5, 5),
+            ],
+            get_players(&board)
+        );
+
+        assert!(!run_turn(&mut board));
+        assert_eq!(
+            vec![
+                Point::new(1, 1),
+                Point::new(2, 2),
+                Point::new(5, 3),
+                Point::new(5, 5),
+            ],
+            get_players(&board)
+        );
+
+        assert_eq!(590, get_hp(&board, &Kind::Goblin));
+    }
+
+    #[test]
+    fn test11() {
+        let board = vec![
+            "#######", "#G..#E#", "#E#E.E#", "#G.##.#", "#...#E#", "#...E.#", "#######",
+        ];
+        assert_eq!(Ok(36334), first(&board))
+    }
+
+    #[test]
+    fn test12() {
+        let board = vec![
+            "#######", "#E..EG#", "#.#G.E#", "#E.##E#", "#G..#.#", "#..E#.#", "#######",
+        ];
+        assert_eq!(Ok(39514), first(&board))
+    }
+
+    #[test]
+    fn test13() {
+        let board = vec![
+            "#######", "#E.G#.#", "#.#G..#", "#G.#.G#", "#G..#.#", "#...E.#", "#######",
+        ];
+        assert_eq!(Ok(27755), first(&board))
+    }
+
+    #[test]
+    fn test14() {
+        let board = vec![
+            "#######", "#.E...#", "#.#..G#", "#.###.#", "#E#G#G#", "#...#G#", "#######",
+        ];
+        assert_eq!(Ok(28944), first(&board))
+    }
+
+    #[test]
+    fn test15() {
+        let board = vec![
+            "#########",
+            "#G......#",
+            "#.E.#...#",
+            "#..##..G#",
+            "#...##..#",
+            "#...#...#",
+            "#.G...G.#",
+            "#.....G.#",
+            "#########",
+        ];
+        assert_eq!(Ok(18740), first(&board))
+    }
+
+    #[test]
+    fn test21() {
+        let board = vec![
+            "#######", "#.G...#", "#...EG#", "#.#.#G#", "#..G#E#", "#.....#", "#######",
+        ];
+        assert_eq!(Ok(4988), second(&board))
+    }
+
+    #[test]
+    fn test22() {
+        let board = vec![
+            "#######", "#E..EG#", "#.#G.E#", "#E.##E#", "#G..#.#", "#..E#.#", "#######",
+        ];
+        assert_eq!(Ok(31284), second(&board))
+    }
+
+    #[test]
+    fn test23() {
+        let board = vec![
+            "#######", "#E.G#.#", "#.#G..#", "#G.#.G#", "#G..#.#", "#...E.#", "#######",
+        ];
+        assert_eq!(Ok(3478), second(&board))
+    }
+
+    #[test]
+    fn test24() {
+        let board = vec![
+            "#######", "#.E...#", "#.#..G#", "#.###.#", "#E#G#G#", "#...#G#", "#######",
+        ];
+        assert_eq!(Ok(6474), second(&board))
+    }
+
+    #[test]
+    fn test25() {
+        let board = vec![
+            "#########",
+            "#G......#",
+            "#.E.#...#",
+            "#..##..G#",
+            "#...##..#",
+            "#...#...#",
+            "#.G...G.#",
+            "#.....G.#",
+            "#########",
+        ];
+        assert_eq!(Ok(1140), second(&board))
+    }
+}
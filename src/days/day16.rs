@@ -0,0 +1,173 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::machine::discovery::*;
+use crate::machine::*;
+use crate::*;
+
+fn first(input: &[&str]) -> Result<usize, ParseErrors> {
+    let (samples, _) = parse_file(input)?;
+    Ok(samples.iter().filter(|s| s.candidates().len() >= 3).count())
+}
+
+fn second(input: &[&str]) -> Result<i32, SolveError> {
+    let (samples, ops) = parse_file(input)?;
+    let mapping = discover(&samples)
+        .unique()
+        .ok_or_else(|| SolveError::unsolvable("the samples don't identify every opcode"))?;
+
+    let mut regs = Registers::new([0; 4]);
+    let mut errors = Vec::new();
+    for (line, instruction) in ops {
+        match decode(&mapping, &instruction) {
+            Some(op) => regs = op.apply(&regs),
+            None => {
+                let number = instruction.0.to_string();
+                let e = ParseError::new(1, &number, "an opcode number the samples identify");
+                errors.push(e.at_line(line));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ParseErrors(errors).into());
+    }
+    Ok(regs.get(&0))
+}
+
+// the samples, and the instructions of the program with their line number
+type File = (Vec<Sample<4>>, Vec<(usize, Instruction)>);
+
+fn parse_file(input: &[&str]) -> Result<File, ParseErrors> {
+    let mut samples = Vec::new();
+    let mut ops = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut before = None;
+    let mut op = None;
+
+    for (i, &line) in input.iter().enumerate() {
+        if line.starts_with("Before") {
+            match parse_registers(line) {
+                Ok(regs) => before = Some(regs),
+                Err(e) => errors.push(e.at_line(i + 1)),
+            }
+        } else if line.starts_with("After") {
+            match parse_registers(line) {
+                Ok(after) => {
+                    if let (Some(before), Some(op)) = (before.take(), op.take()) {
+                        samples.push(Sample {
+                            before,
+                            instruction: op,
+                            after,
+                        });
+                    }
+                }
+                Err(e) => errors.push(e.at_line(i + 1)),
+            }
+        } else if !line.is_empty() {
+            match parse_instruction(line) {
+                Ok(instruction) if before.is_some() => op = Some(instruction),
+                Ok(instruction) => ops.push((i + 1, instruction)),
+                Err(e) => errors.push(e.at_line(i + 1)),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok((samples, ops))
+    } else {
+        Err(ParseErrors(errors))
+    }
+}
+
+type SmallRegisters = Registers<4>;
+
+lazy_static! {
+    static ref REGISTERS_RE: Regex = Regex::new(r"\[(\d+), (\d+), (\d+), (\d+)\]").unwrap();
+    static ref OP_RE: Regex = Regex::new(r"^(\d+) (\d+) (\d+) (\d+)$").unwrap();
+}
+
+fn parse_registers(input: &str) -> Result<SmallRegisters, ParseError> {
+    let cap = REGISTERS_RE
+        .captures(input)
+        .ok_or_else(|| ParseError::no_match(input, &REGISTERS_RE))?;
+    let a = parse_capture(&cap, 1, "a")?;
+    let b = parse_capture(&cap, 2, "b")?;
+    let c = parse_capture(&cap, 3, "c")?;
+    let d = parse_capture(&cap, 4, "d")?;
+    Ok(Registers::new([a, b, c, d]))
+}
+
+fn parse_instruction(input: &str) -> Result<Instruction, ParseError> {
+    let cap = OP_RE
+        .captures(input)
+        .ok_or_else(|| ParseError::no_match(input, &OP_RE))?;
+    let o = parse_capture(&cap, 1, "o")?;
+    let a = parse_capture(&cap, 2, "a")?;
+    let b = parse_capture(&cap, 3, "b")?;
+    let c = parse_capture(&cap, 4, "c")?;
+    Ok((o, a, b, c))
+}
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    impl Sample<4> {
+        fn parse(before: &str, after: &str, op: &str) -> Result<Sample<4>, ParseError> {
+            let before = parse_registers(before)?;
+            let after = parse_registers(after)?;
+            let instruction = parse_instruction(op)?;
+            Ok(Sample {
+                before,
+                instruction,
+                after,
+            })
+        }
+    }
+
+    #[test]
+    fn test_count_ops() {
+        let input = Sample::parse("[3, 2, 1, 1]", "[3, 2, 2, 1]", "9 2 1 2").unwrap();
+
+        assert_eq!(3, input.candidates().len());
+    }
+
+    #[test]
+    fn test_unidentified() {
+        // only addr turns 3 and 5 into 8
+        let mut input = vec![
+            "Before: [3, 5, 0, 0]",
+            "0 0 1 2",
+            "After:  [3, 5, 8, 0]",
+            "",
+            "0 0 1 0",
+            "4 0 0 0",
+        ];
+        assert_eq!(
+            "line 6, column 1: expected an opcode number the samples identify, found \"4\"",
+            second(&input).err().unwrap().to_string()
+        );
+
+        input.pop();
+        assert_eq!(Ok(0), second(&input));
+
+        input[1] = "0 0 0 2";
+        assert_eq!(
+            "the samples don't identify every opcode",
+            second(&input).err().unwrap().to_string()
+        );
+    }
+}
@@ -1,28 +1,28 @@
-use adventofcode2018::*;
+use crate::*;
 
 use std::collections::HashMap;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-fn first(input: &[&str]) -> usize {
-    let mut board = Board::parse(input);
+fn first(input: &[&str]) -> Result<usize, ParseErrors> {
+    let mut board = Board::parse(input)?;
     board.drop_water();
-    board
+    Ok(board
         .board
         .iter()
         .filter(|(_, t)| matches!(t, Tile::Water | Tile::Path))
-        .count()
+        .count())
 }
 
-fn second(input: &[&str]) -> usize {
-    let mut board = Board::parse(input);
+fn second(input: &[&str]) -> Result<usize, ParseErrors> {
+    let mut board = Board::parse(input)?;
     board.drop_water();
-    board
+    Ok(board
         .board
         .iter()
         .filter(|(_, t)| matches!(t, Tile::Water))
-        .count()
+        .count())
 }
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
@@ -48,48 +48,60 @@ struct Board {
 }
 
 lazy_static! {
-    static ref LINE_RE: Regex = Regex::new(r"(x|y)=(\d+), .=(\d+)..(\d+)").unwrap();
+    static ref LINE_RE: Regex = Regex::new(r"^(x|y)=(\d+), .=(\d+)\.\.(\d+)$").unwrap();
+}
+
+struct Vein {
+    vertical: bool,
+    left_idx: usize,
+    right_start: usize,
+    right_end: usize,
+}
+
+impl FromLine for Vein {
+    fn from_line(line: &str) -> Result<Vein, ParseError> {
+        let cap = LINE_RE
+            .captures(line)
+            .ok_or_else(|| ParseError::no_match(line, &LINE_RE))?;
+        Ok(Vein {
+            vertical: parse_capture::<char>(&cap, 1, "left")? == 'x',
+            left_idx: parse_capture(&cap, 2, "left_idx")?,
+            right_start: parse_capture(&cap, 3, "right_start")?,
+            right_end: parse_capture(&cap, 4, "right_end")?,
+        })
+    }
 }
 
 impl Board {
-    fn parse(input: &[&str]) -> Board {
+    fn parse(input: &[&str]) -> Result<Board, ParseErrors> {
+        let veins: Vec<Vein> = parse_lines(input)?;
+
         let mut board = HashMap::new();
         let mut floor = 0;
         let mut ceiling = 100;
-        for &line in input {
-            if let Some(cap) = LINE_RE.captures(line) {
-                let left = parse_capture(&cap, 1, "left").unwrap();
-                let left_idx = parse_capture(&cap, 2, "left_idx").unwrap();
-                let right_start = parse_capture(&cap, 3, "right_start").unwrap();
-                let right_end = parse_capture(&cap, 4, "right_end").unwrap();
-
-                match left {
-                    'x' => {
-                        let x = left_idx;
-                        for y in right_start..=right_end {
-                            board.insert(Coord::new(x, y), Tile::Clay);
-                            floor = floor.max(y);
-                            ceiling = ceiling.min(y);
-                        }
-                    }
-                    'y' => {
-                        let y = left_idx;
-                        floor = floor.max(y);
-                        ceiling = ceiling.min(y);
-                        for x in right_start..=right_end {
-                            board.insert(Coord::new(x, y), Tile::Clay);
-                        }
-                    }
-                    _ => panic!("invalid left pattern"),
+        for vein in veins {
+            if vein.vertical {
+                let x = vein.left_idx;
+                for y in vein.right_start..=vein.right_end {
+                    board.insert(Coord::new(x, y), Tile::Clay);
+                    floor = floor.max(y);
+                    ceiling = ceiling.min(y);
+                }
+            } else {
+                let y = vein.left_idx;
+                floor = floor.max(y);
+                ceiling = ceiling.min(y);
+                for x in vein.right_start..=vein.right_end {
+                    board.insert(Coord::new(x, y), Tile::Clay);
                 }
             }
         }
         floor += 1;
-        Board {
+        Ok(Board {
             board,
             floor,
             ceiling,
-        }
+        })
     }
 
     fn get_tile(&self, pos: &Coord) -> Tile {
@@ -189,22 +201,22 @@ enum Tile {
     Path,
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+pub struct Day17;
 
-    let input = read_file("17");
-    let input: Vec<&str> = input.trim().split('\n').collect();
-
-    println!("{}", first(&input));
-    println!("{}", second(&input));
+impl Solution for Day17 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::Tile::{Clay, Path, Sand, Water};
     use super::*;
-    use crate::Tile::{Clay, Path, Sand, Water};
 
     lazy_static! {
         static ref VEINS: Vec<&'static str> = vec![
@@ -257,18 +269,18 @@ mod test {
             "...|#######|..",
         ];
 
-        let mut board = Board::parse(&VEINS);
+        let mut board = Board::parse(&VEINS).unwrap();
         board.drop_water();
         check_board(&check, &board);
     }
 
     #[test]
     fn test1() {
-        assert_eq!(57, first(&VEINS));
+        assert_eq!(Ok(57), first(&VEINS));
     }
 
     #[test]
     fn test2() {
-        assert_eq!(29, second(&VEINS));
+        assert_eq!(Ok(29), second(&VEINS));
     }
 }
@@ -1,24 +1,25 @@
-use adventofcode2018::*;
+use crate::grid::{Grid, Point};
+use crate::*;
 use std::collections::HashMap;
 
-fn first(input: &[&str]) -> usize {
-    let mut area = CollectionArea::parse(input).unwrap();
+fn first(input: &[&str]) -> Result<usize, ParseErrors> {
+    let mut area = CollectionArea::parse(input)?;
     for _ in 0..10 {
         area = area.next();
     }
-    area.resource_value()
+    Ok(area.resource_value())
 }
 
-fn second(input: &[&str]) -> usize {
+fn second(input: &[&str]) -> Result<usize, ParseErrors> {
     const ITERATIONS: usize = 1_000_000_000;
-    let mut area = CollectionArea::parse(input).unwrap();
+    let mut area = CollectionArea::parse(input)?;
 
     let mut cache = HashMap::new();
     let mut res_cache = Vec::new();
     let mut idx = 0;
     loop {
         if idx == ITERATIONS {
-            return area.resource_value();
+            return Ok(area.resource_value());
         }
 
         if cache.contains_key(&area) {
@@ -37,7 +38,7 @@ fn second(input: &[&str]) -> usize {
     let loop_size = idx - loop_start;
     let remainder = (ITERATIONS - loop_start) % loop_size;
 
-    res_cache[loop_start + remainder]
+    Ok(res_cache[loop_start + remainder])
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -48,12 +49,12 @@ enum Acre {
 }
 
 impl Acre {
-    fn parse(input: &char) -> Result<Acre, String> {
+    fn parse(input: &char) -> Result<Acre, ParseError> {
         match input {
             '.' => Ok(Acre::OpenGround),
             '|' => Ok(Acre::Trees),
             '#' => Ok(Acre::Lumberyard),
-            c => Err(format!("can't parse {} as Acre", c)),
+            c => Err(ParseError::new(1, &c.to_string(), "one of '.', '|', '#'")),
         }
     }
 
@@ -87,56 +88,28 @@ impl Acre {
 
 #[derive(Clone, Eq, PartialEq, Hash)]
 struct CollectionArea {
-    inner: Vec<Vec<Acre>>,
+    inner: Grid<Acre>,
 }
 
 impl CollectionArea {
-    fn parse(input: &[&str]) -> Result<CollectionArea, String> {
-        let inner = input
-            .iter()
-            .map(|line| line.chars().map(|c| Acre::parse(&c)).collect())
-            .collect::<Result<Vec<Vec<Acre>>, String>>()?;
+    fn parse(input: &[&str]) -> Result<CollectionArea, ParseErrors> {
+        let inner = Grid::parse_with(input, |c| Acre::parse(&c))?;
         Ok(CollectionArea { inner })
     }
 
     fn next(&self) -> CollectionArea {
-        let new = self
-            .inner
-            .iter()
-            .enumerate()
-            .map(|(row, line)| {
-                line.iter()
-                    .enumerate()
-                    .map(|(col, a)| {
-                        let adj = self.adjacent(row, col);
-                        a.next_state(&adj)
-                    })
-                    .collect()
-            })
-            .collect();
+        let new = self.inner.map(|p, a| {
+            let adj = self.adjacent(&p);
+            a.next_state(&adj)
+        });
         CollectionArea { inner: new }
     }
 
-    fn adjacent(&self, row: usize, col: usize) -> Vec<&Acre> {
-        let mut res = Vec::new();
-
-        let minr = if row > 0 { row - 1 } else { row };
-        let maxr = if row < self.inner.len() - 1 {
-            row + 1
-        } else {
-            row
-        };
-        for curr_row in minr..=maxr {
-            let line = &self.inner[curr_row];
-            let minc = if col > 0 { col - 1 } else { col };
-            let maxc = if col < line.len() - 1 { col + 1 } else { col };
-            for (curr_col, acre) in line.iter().enumerate().take(maxc + 1).skip(minc) {
-                if curr_row != row || curr_col != col {
-                    res.push(acre);
-                }
-            }
-        }
-        res
+    fn adjacent(&self, p: &Point) -> Vec<&Acre> {
+        self.inner
+            .neighbours8(p)
+            .filter_map(|n| self.inner.get(&n))
+            .collect()
     }
 
     fn resource_value(&self) -> usize {
@@ -152,21 +125,21 @@ impl CollectionArea {
         trees * lumberyards
     }
 
-    fn iter(&self) -> Box<dyn Iterator<Item = Acre> + '_> {
-        Box::new(self.inner.iter().flatten().copied())
+    fn iter(&self) -> impl Iterator<Item = Acre> + '_ {
+        self.inner.values().copied()
     }
 }
 
-fn main() {
-    let start = std::time::Instant::now();
+pub struct Day18;
 
-    let input = read_file("18");
-    let input: Vec<&str> = input.trim().split('\n').collect();
-
-    println!("{}", first(&input));
-    println!("{}", second(&input));
+impl Solution for Day18 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
 
-    println!("elapsed {:?}", start.elapsed());
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
 }
 
 #[cfg(test)]
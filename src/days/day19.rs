@@ -0,0 +1,216 @@
+use crate::machine::*;
+use crate::*;
+
+use std::convert::TryFrom;
+
+fn first(input: &[&str]) -> Result<usize, ParseErrors> {
+    let mut machine = Machine::<6>::parse_strict(input)?;
+    machine.optimize();
+    machine.set_engine(Engine::Compiled);
+    machine.run();
+    Ok(machine.registers.get(&0) as usize)
+}
+
+fn second(input: &[&str]) -> Result<usize, SolveError> {
+    let machine = Machine::<6>::parse_strict(input)?;
+    fast_run(machine, 1)
+}
+
+// the program computes a target number in a setup block, then jumps back to the start
+// of a nested loop that sums its divisors: run the setup, then do the sum natively of
+// the register the loop compares its products with
+fn fast_run(mut machine: Machine<6>, reg0: i32) -> Result<usize, SolveError> {
+    machine.registers.set(&0, reg0);
+    let target = optimizer::detect(machine.program(), machine.ip_reg, 6)
+        .into_iter()
+        .find_map(|idiom| match idiom {
+            Some(optimizer::Idiom::DivisorLoop { n, .. }) => Some(n),
+            _ => None,
+        });
+
+    let mut ip = machine.curr_ip();
+    loop {
+        machine.execute_step();
+        if machine.state != MachineState::Active {
+            return Ok(machine.registers.get(&0) as usize);
+        }
+
+        let next = machine.curr_ip();
+        if next <= ip {
+            break;
+        }
+        ip = next;
+    }
+
+    let target = target.ok_or_else(|| SolveError::unsolvable("no loop sums divisors"))?;
+    let n = machine.registers.get(&target);
+    let n = usize::try_from(n)
+        .map_err(|_| SolveError::unsolvable("the number to divide isn't positive"))?;
+    Ok(sum_of_divisors(n))
+}
+
+// `is_multiple_of` would need Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn sum_of_divisors(n: usize) -> usize {
+    (1..)
+        .take_while(|i| i * i <= n)
+        .filter(|i| n % i == 0)
+        .map(|i| if i * i == n { i } else { i + n / i })
+        .sum()
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let input: Vec<&str> = "#ip 0
+seti 5 0 1
+seti 6 0 2
+addi 0 1 0
+addr 1 2 3
+setr 1 0 0
+seti 8 0 4
+seti 9 0 5"
+            .split('\n')
+            .collect();
+
+        let mut machine = Machine::<6>::parse(&input).unwrap();
+        assert_eq!(machine.registers.all(), [0, 0, 0, 0, 0, 0]);
+
+        machine.execute_step();
+        assert_eq!(machine.state, MachineState::Active);
+        assert_eq!(machine.registers.all(), [1, 5, 0, 0, 0, 0]);
+
+        machine.execute_step();
+        assert_eq!(machine.state, MachineState::Active);
+        assert_eq!(machine.registers.all(), [2, 5, 6, 0, 0, 0]);
+
+        machine.execute_step();
+        assert_eq!(machine.state, MachineState::Active);
+        assert_eq!(machine.registers.all(), [4, 5, 6, 0, 0, 0]);
+
+        machine.execute_step();
+        assert_eq!(machine.state, MachineState::Active);
+        assert_eq!(machine.registers.all(), [6, 5, 6, 0, 0, 0]);
+
+        machine.execute_step();
+        assert_eq!(machine.state, MachineState::Active);
+        assert_eq!(machine.registers.all(), [7, 5, 6, 0, 0, 9]);
+
+        machine.execute_step();
+        assert_eq!(machine.state, MachineState::Halted);
+    }
+
+    const DIVISORS: [&str; 37] = [
+        "#ip 5",
+        "addi 5 16 5",
+        "seti 1 5 3",
+        "seti 1 4 2",
+        "mulr 3 2 4",
+        "eqrr 4 1 4",
+        "addr 4 5 5",
+        "addi 5 1 5",
+        "addr 3 0 0",
+        "addi 2 1 2",
+        "gtrr 2 1 4",
+        "addr 5 4 5",
+        "seti 2 2 5",
+        "addi 3 1 3",
+        "gtrr 3 1 4",
+        "addr 4 5 5",
+        "seti 1 0 5",
+        "mulr 5 5 5",
+        "addi 1 2 1",
+        "mulr 1 1 1",
+        "mulr 5 1 1",
+        "muli 1 1 1",
+        "addi 4 1 4",
+        "mulr 4 5 4",
+        "addi 4 9 4",
+        "addr 1 4 1",
+        "addr 5 0 5",
+        "seti 0 5 5",
+        "setr 5 6 4",
+        "addi 4 0 4",
+        "addr 5 4 4",
+        "addi 4 0 4",
+        "muli 4 2 4",
+        "addi 4 0 4",
+        "addr 1 4 1",
+        "seti 0 3 0",
+        "seti 0 1 5",
+    ];
+
+    #[test]
+    fn test_sum_of_divisors() {
+        assert_eq!(1, sum_of_divisors(1));
+        assert_eq!(12, sum_of_divisors(6));
+        assert_eq!(31, sum_of_divisors(16));
+        assert_eq!(296, sum_of_divisors(219));
+    }
+
+    #[test]
+    fn test_fast_run() {
+        for &(reg0, expected) in [(0, 108), (1, 296)].iter() {
+            let mut machine = Machine::<6>::parse(&DIVISORS).unwrap();
+            machine.registers.set(&0, reg0);
+            machine.run();
+            assert_eq!(expected, machine.registers.get(&0) as usize);
+
+            let machine = Machine::<6>::parse(&DIVISORS).unwrap();
+            assert_eq!(Ok(expected), fast_run(machine, reg0));
+        }
+    }
+
+    #[test]
+    fn test_fast_run_target() {
+        // the setup leaves a larger value than the target in r2, which the loop resets
+        let mut input = DIVISORS;
+        input[31] = "seti 100000 0 2";
+        let mut machine = Machine::<6>::parse(&input).unwrap();
+        machine.registers.set(&0, 1);
+        machine.run();
+        assert_eq!(296, machine.registers.get(&0));
+
+        let machine = Machine::<6>::parse(&input).unwrap();
+        assert_eq!(Ok(296), fast_run(machine, 1));
+
+        // loops forever without summing divisors
+        let machine = Machine::<6>::parse(&["#ip 1", "addi 0 1 0", "seti 0 0 1"]).unwrap();
+        assert_eq!(
+            "no loop sums divisors",
+            fast_run(machine, 1).err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_fast_run_halting() {
+        let input: Vec<&str> = "#ip 0
+seti 5 0 1
+seti 6 0 2
+addi 0 1 0
+addr 1 2 3
+setr 1 0 0
+seti 8 0 4
+seti 9 0 5"
+            .split('\n')
+            .collect();
+
+        let machine = Machine::<6>::parse(&input).unwrap();
+        assert_eq!(first(&input).unwrap(), fast_run(machine, 0).unwrap());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::grid::Point;
+use crate::search::{bfs, Reached, Space};
+use crate::*;
+use std::collections::{BTreeSet, HashMap};
+
+fn first(input: &str) -> usize {
+    let reached = distances(input);
+    reached.iter().map(|(_, d)| d).max().unwrap_or(0) as usize
+}
+
+fn second(input: &str) -> usize {
+    let reached = distances(input);
+    reached.iter().filter(|&(_, d)| d >= 1000).count()
+}
+
+fn distances(input: &str) -> Reached<Point> {
+    let origin = Point::new(0, 0);
+    bfs(&Facility::parse(input, origin), origin)
+}
+
+// the doors out of every room
+struct Facility {
+    doors: HashMap<Point, Vec<Point>>,
+}
+
+impl Facility {
+    // follows every branch at once, from the set of rooms the route can be in
+    fn parse(input: &str, origin: Point) -> Facility {
+        let mut facility = Facility {
+            doors: HashMap::new(),
+        };
+        let mut rooms = BTreeSet::new();
+        rooms.insert(origin);
+        // for each open group, the rooms it started from and those its finished options
+        // led to
+        let mut groups: Vec<(BTreeSet<Point>, BTreeSet<Point>)> = Vec::new();
+
+        for c in input.chars() {
+            let (dx, dy) = match c {
+                'N' => (0, -1),
+                'W' => (-1, 0),
+                'S' => (0, 1),
+                'E' => (1, 0),
+                '(' => {
+                    groups.push((rooms.clone(), BTreeSet::new()));
+                    continue;
+                }
+                '|' => {
+                    if let Some((starts, ends)) = groups.last_mut() {
+                        ends.extend(rooms);
+                        rooms = starts.clone();
+                    }
+                    continue;
+                }
+                ')' => {
+                    if let Some((_, ends)) = groups.pop() {
+                        rooms.extend(ends);
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            rooms = rooms
+                .iter()
+                .map(|room| {
+                    let next = room.offset(dx, dy);
+                    facility.connect(*room, next);
+                    next
+                })
+                .collect();
+        }
+        facility
+    }
+
+    fn connect(&mut self, a: Point, b: Point) {
+        let doors = self.doors.entry(a).or_default();
+        if !doors.contains(&b) {
+            doors.push(b);
+            self.doors.entry(b).or_default().push(a);
+        }
+    }
+}
+
+impl Space for Facility {
+    type State = Point;
+
+    fn neighbours(&self, room: &Point) -> Vec<(Point, u32)> {
+        self.doors
+            .get(room)
+            .map_or_else(Vec::new, |d| d.iter().map(|&r| (r, 1)).collect())
+    }
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(input.trim()).to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(input.trim()).to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test11() {
+        let input = "^WNE$";
+        let max = first(input);
+        assert_eq!(max, 3);
+    }
+
+    #[test]
+    fn test12() {
+        let input = "^ENWWW(NEEE|SSE(EE|N))$";
+        let max = first(input);
+        assert_eq!(max, 10);
+    }
+
+    #[test]
+    fn test13() {
+        let input = "^ENNWSWW(NEWS|)SSSEEN(WNSE|)EE(SWEN|)NNN$";
+        let max = first(input);
+        assert_eq!(max, 18);
+    }
+
+    #[test]
+    fn test14() {
+        let input = "^ESSWWN(E|NNENN(EESS(WNSE|)SSS|WWWSSSSE(SW|NNNE)))$";
+        let max = first(input);
+        assert_eq!(max, 23);
+    }
+
+    #[test]
+    fn test15() {
+        let input = "^WSSEESWWWNW(S|NENNEEEENN(ESSSSW(NWSW|SSEN)|WSWWN(E|WWS(E|SS))))$";
+        let max = first(input);
+        assert_eq!(max, 31);
+    }
+
+    #[test]
+    fn test_rooms() {
+        let input = "^ENWWW(NEEE|SSE(EE|N))$";
+        let reached = distances(input);
+        assert_eq!(16, reached.len());
+        assert_eq!(Some(9), reached.moves(&Point::new(1, -2)));
+        assert_eq!(Some(10), reached.moves(&Point::new(1, 1)));
+
+        // the route loops back through the room of the detour, which is a shortcut
+        let input = "^N(E|)NESSS(WS|)$";
+        let reached = distances(input);
+        assert_eq!(Some(2), reached.moves(&Point::new(1, -1)));
+        assert_eq!(Some(4), reached.moves(&Point::new(1, 1)));
+        assert_eq!(6, first(input));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::machine::*;
+use crate::*;
+
+// r0 is only ever compared against, so the soonest halt is the first path on which that
+// comparison succeeds
+fn first(input: &[&str]) -> Result<i32, SolveError> {
+    let machine = Machine::<6>::parse_strict(input)?;
+    comparison(machine.program()).ok_or_else(no_comparison)?;
+    let exploration = machine.explore(&[0], 1_000_000, 1);
+    exploration
+        .halts
+        .first()
+        .and_then(|halt| halt.value(0))
+        .ok_or_else(|| SolveError::unsolvable("no halt for a value of r0 the analysis can tell"))
+}
+
+fn no_comparison() -> SolveError {
+    SolveError::unsolvable("no single eqrr compares r0 with another register")
+}
+
+// the `eqrr` comparing r0 with the register holding the candidate value, as (ip, register),
+// when there is a single one
+fn comparison(program: &[OpCode]) -> Option<(i32, i32)> {
+    let mut found = program.iter().enumerate().filter_map(|(ip, op)| match *op {
+        OpCode::EqRr(0, r, _) | OpCode::EqRr(r, 0, _) if r != 0 => Some((ip as i32, r)),
+        _ => None,
+    });
+    let res = found.next()?;
+    match found.next() {
+        Some(_) => None,
+        None => Some(res),
+    }
+}
+
+// the program halts when r0 matches the candidate value at the comparison, which depends
+// only on its previous value: the answer is the last value before they start repeating
+fn second(input: &[&str]) -> Result<i32, SolveError> {
+    let mut machine = Machine::<6>::parse_strict(input)?;
+    let (ip, r) = comparison(machine.program()).ok_or_else(no_comparison)?;
+    machine.optimize();
+    machine.set_engine(Engine::Compiled);
+    let cycle = machine
+        .find_cycle_by(ip, |regs| regs.get(&r))
+        .ok_or_else(|| SolveError::unsolvable("the candidate values never repeat"))?;
+    Ok(cycle.values[cycle.values.len() - 1])
+}
+
+pub struct Day21;
+
+impl Solution for Day21 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // walks through all the values of r3 = (5 * r3 + 3) & 255 from 7, halting when one of
+    // them plus 256 matches r0; the last one before they repeat is the seed itself
+    const LCG: [&str; 9] = [
+        "#ip 5",
+        "seti 7 0 3",
+        "muli 3 5 3",
+        "addi 3 3 3",
+        "bani 3 255 3",
+        "bori 3 256 1",
+        "eqrr 1 0 2",
+        "addr 2 5 5",
+        "seti 0 0 5",
+    ];
+
+    // the same with other registers and the operands of the comparison swapped
+    const VARIANT: [&str; 9] = [
+        "#ip 2",
+        "seti 7 0 4",
+        "muli 4 5 4",
+        "addi 4 3 4",
+        "bani 4 255 4",
+        "bori 4 256 5",
+        "eqrr 0 5 1",
+        "addr 1 2 2",
+        "seti 0 0 2",
+    ];
+
+    #[test]
+    fn test_comparison() {
+        let machine = Machine::<6>::parse(&LCG).unwrap();
+        assert_eq!(Some((5, 1)), comparison(machine.program()));
+        let machine = Machine::<6>::parse(&VARIANT).unwrap();
+        assert_eq!(Some((5, 5)), comparison(machine.program()));
+
+        let machine = Machine::<6>::parse(&[LCG[0], LCG[6], LCG[6]]).unwrap();
+        assert_eq!(None, comparison(machine.program()));
+    }
+
+    #[test]
+    fn test_variants() {
+        let malformed = first(&["#ip 5", "seti 7 0"]);
+        assert!(matches!(malformed, Err(SolveError::Parse(_))));
+
+        for input in [&LCG, &VARIANT] {
+            assert_eq!(294, first(input).unwrap());
+            assert_eq!(263, second(input).unwrap());
+        }
+
+        let input = [LCG[0], LCG[1], LCG[7], LCG[8]];
+        let expected = "no single eqrr compares r0 with another register";
+        assert!(matches!(first(&input), Err(SolveError::Unsolvable(_))));
+        assert_eq!(expected, first(&input).err().unwrap().to_string());
+        assert_eq!(expected, second(&input).err().unwrap().to_string());
+
+        // compares, but halts before ever getting there
+        let input = ["#ip 5", "seti 7 0 3", "seti 9 0 5", "eqrr 3 0 2"];
+        assert_eq!(
+            "no halt for a value of r0 the analysis can tell",
+            first(&input).err().unwrap().to_string()
+        );
+        assert_eq!(
+            "the candidate values never repeat",
+            second(&input).err().unwrap().to_string()
+        );
+    }
+}
@@ -0,0 +1,384 @@
+use std::cell::RefCell;
+
+use crate::grid::{Grid, Point};
+use crate::search::{dijkstra, Path, Space};
+use crate::*;
+
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+fn first(depth: u32, target: (usize, usize)) -> u32 {
+    let cave = Cave::new(depth, target);
+    cave.risk_level()
+}
+
+fn second(depth: u32, target: (usize, usize)) -> u32 {
+    Route::find(depth, target).minutes()
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct Location {
+    pub pos: (usize, usize),
+    pub tool: Tool,
+}
+
+// the fastest way to reach the target holding the torch, as a search over positions and
+// equipped tools; the cave extends as far as the search wanders
+pub struct Route {
+    cave: Cave,
+    pub path: Path<Location>,
+}
+
+impl Route {
+    pub fn parse(input: &str) -> Result<Route, ParseErrors> {
+        let (depth, target) = parse(input)?;
+        Ok(Route::find(depth, target))
+    }
+
+    fn find(depth: u32, target: (usize, usize)) -> Route {
+        let cave = Cave::new(depth, target);
+        let start = Location {
+            pos: (0, 0),
+            tool: Tool::Torch,
+        };
+        let goal = Location {
+            pos: target,
+            tool: Tool::Torch,
+        };
+        // any two region types share a tool, so there's always a way from one region to
+        // the next and the search can't fail
+        let path = dijkstra(&cave, start, |&l| l == goal);
+        let path = path.expect("the target can be reached");
+        Route { cave, path }
+    }
+
+    pub fn minutes(&self) -> u32 {
+        self.path.cost
+    }
+
+    // the minute each location of the path is reached at
+    pub fn times(&self) -> Vec<u32> {
+        let mut res = vec![0];
+        for (a, b) in self.path.states.iter().tuple_windows() {
+            let step = if a.tool == b.tool { 1 } else { 7 };
+            res.push(res.last().unwrap() + step);
+        }
+        res
+    }
+
+    // the cave around the route, with `.`, `=` and `|` for rocky, wet and narrow regions,
+    // `M` and `X` for the mouth and the target, and the route by the tool it is walked
+    // with, `t`, `g` or `n` for the torch, the climbing gear or neither; the uppercase
+    // letters mark where the tool was switched. With a minute, only the part of the route
+    // walked by then is drawn, and `@` marks where the rescuer is
+    pub fn render(&self, minute: Option<u32>) -> String {
+        self.draw(minute).render_with(|&c| c)
+    }
+
+    // the same as `render`, as a binary PPM image with `scale` pixels per region
+    pub fn render_ppm(&self, minute: Option<u32>, scale: usize) -> Vec<u8> {
+        let grid = self.draw(minute);
+        let (width, height) = (grid.width() * scale, grid.height() * scale);
+        let mut res = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for y in 0..height {
+            for x in 0..width {
+                let p = Point::new((x / scale) as isize, (y / scale) as isize);
+                res.extend_from_slice(&colour(grid.get(&p).copied().unwrap_or(' ')));
+            }
+        }
+        res
+    }
+
+    fn draw(&self, minute: Option<u32>) -> Grid<char> {
+        let states = &self.path.states;
+        let walked = match minute {
+            Some(m) => self.times().iter().take_while(|&&t| t <= m).count(),
+            None => states.len(),
+        };
+
+        let (width, height) = states.iter().fold(self.cave.target, |(w, h), l| {
+            (w.max(l.pos.0), h.max(l.pos.1))
+        });
+        let mut grid = Grid::from_fn(width + 2, height + 2, |p| {
+            match self.cave.region_type((p.x as usize, p.y as usize)) {
+                RegionType::Rocky => '.',
+                RegionType::Wet => '=',
+                RegionType::Narrow => '|',
+            }
+        });
+
+        let at = |(x, y): (usize, usize)| Point::new(x as isize, y as isize);
+        for (i, l) in states[..walked].iter().enumerate() {
+            let c = match l.tool {
+                Tool::Torch => 't',
+                Tool::ClimbingGear => 'g',
+                Tool::None => 'n',
+            };
+            let switched = i > 0 && states[i - 1].pos == l.pos;
+            grid.set(
+                &at(l.pos),
+                if switched { c.to_ascii_uppercase() } else { c },
+            );
+        }
+        grid.set(&at((0, 0)), 'M');
+        grid.set(&at(self.cave.target), 'X');
+        if minute.is_some() {
+            grid.set(&at(states[walked - 1].pos), '@');
+        }
+        grid
+    }
+}
+
+fn colour(c: char) -> [u8; 3] {
+    match c {
+        '.' => [136, 136, 136],
+        '=' => [64, 112, 200],
+        '|' => [150, 110, 70],
+        't' | 'T' => [255, 200, 0],
+        'g' | 'G' => [220, 40, 40],
+        'n' | 'N' => [250, 250, 250],
+        'M' | 'X' => [40, 200, 80],
+        _ => [255, 0, 255],
+    }
+}
+
+impl Space for Cave {
+    type State = Location;
+
+    // switching to the other tool allowed in the region, or moving to a region where the
+    // current one is allowed
+    fn neighbours(&self, &Location { pos, tool }: &Location) -> Vec<(Location, u32)> {
+        let tools = valid_tools(self.region_type(pos));
+        let other = if tools[0] == tool { tools[1] } else { tools[0] };
+        let mut res = vec![(Location { pos, tool: other }, 7)];
+
+        let (x, y) = pos;
+        let mut next = vec![(x + 1, y), (x, y + 1)];
+        if x > 0 {
+            next.push((x - 1, y));
+        }
+        if y > 0 {
+            next.push((x, y - 1));
+        }
+        for n in next {
+            if valid_tools(self.region_type(n)).contains(&tool) {
+                res.push((Location { pos: n, tool }, 1));
+            }
+        }
+        res
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Tool {
+    Torch,
+    ClimbingGear,
+    None,
+}
+
+fn valid_tools(t: RegionType) -> [Tool; 2] {
+    match t {
+        RegionType::Rocky => [Tool::Torch, Tool::ClimbingGear],
+        RegionType::Wet => [Tool::ClimbingGear, Tool::None],
+        RegionType::Narrow => [Tool::Torch, Tool::None],
+    }
+}
+
+// erosion levels are only computed when asked for, and remembered by column
+struct Cave {
+    depth: u32,
+    target: (usize, usize),
+    erosion: RefCell<Vec<Vec<u32>>>,
+}
+
+impl Cave {
+    fn new(depth: u32, target: (usize, usize)) -> Cave {
+        Cave {
+            depth,
+            target,
+            erosion: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn erosion(&self, coord: (usize, usize)) -> u32 {
+        let (x, y) = coord;
+        if let Some(&e) = self.erosion.borrow().get(x).and_then(|col| col.get(y)) {
+            return e;
+        }
+        // every region depends on the ones above and left of it, so the columns up to x are
+        // grown down to y in order instead of recursing
+        let mut map = self.erosion.borrow_mut();
+        if map.len() <= x {
+            map.resize(x + 1, Vec::new());
+        }
+        for i in 0..=x {
+            for j in map[i].len()..=y {
+                let index = match (i, j) {
+                    (0, 0) => 0,
+                    t if t == self.target => 0,
+                    (i, 0) => i as u32 * 16807,
+                    (0, j) => j as u32 * 48271,
+                    (i, j) => map[i - 1][j] * map[i][j - 1],
+                };
+                map[i].push((index + self.depth) % 20183);
+            }
+        }
+        map[x][y]
+    }
+
+    fn region_type(&self, coord: (usize, usize)) -> RegionType {
+        erosion_to_type(self.erosion(coord))
+    }
+
+    // of the rectangle from the mouth to the target
+    fn risk_level(&self) -> u32 {
+        (0..=self.target.0)
+            .cartesian_product(0..=self.target.1)
+            .map(|coord| match self.region_type(coord) {
+                RegionType::Wet => 1,
+                RegionType::Narrow => 2,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+#[derive(PartialEq, Debug)]
+enum RegionType {
+    Rocky,
+    Wet,
+    Narrow,
+}
+
+fn erosion_to_type(erosion_level: u32) -> RegionType {
+    match erosion_level % 3 {
+        0 => RegionType::Rocky,
+        1 => RegionType::Wet,
+        _ => RegionType::Narrow,
+    }
+}
+
+lazy_static! {
+    static ref DEPTH_RE: Regex = Regex::new(r"^depth: (\d+)$").unwrap();
+    static ref TARGET_RE: Regex = Regex::new(r"^target: (\d+),(\d+)$").unwrap();
+}
+
+fn parse(input: &str) -> Result<(u32, (usize, usize)), ParseErrors> {
+    let input = lines(input);
+
+    let parse_depth = |line: &str| -> Result<u32, ParseError> {
+        let cap = DEPTH_RE
+            .captures(line)
+            .ok_or_else(|| ParseError::no_match(line, &DEPTH_RE))?;
+        parse_capture(&cap, 1, "depth")
+    };
+    let parse_target = |line: &str| -> Result<(usize, usize), ParseError> {
+        let cap = TARGET_RE
+            .captures(line)
+            .ok_or_else(|| ParseError::no_match(line, &TARGET_RE))?;
+        Ok((parse_capture(&cap, 1, "x")?, parse_capture(&cap, 2, "y")?))
+    };
+
+    let depth = parse_depth(input.first().cloned().unwrap_or(""));
+    let target = parse_target(input.get(1).cloned().unwrap_or(""));
+
+    match (depth, target) {
+        (Ok(depth), Ok(target)) => Ok((depth, target)),
+        (depth, target) => {
+            let mut errors = Vec::new();
+            if let Err(e) = depth {
+                errors.push(e.at_line(1));
+            }
+            if let Err(e) = target {
+                errors.push(e.at_line(2));
+            }
+            Err(ParseErrors(errors))
+        }
+    }
+}
+
+pub struct Day22;
+
+impl Solution for Day22 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        let (depth, target) = parse(input)?;
+        Ok(first(depth, target).to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        let (depth, target) = parse(input)?;
+        Ok(second(depth, target).to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let res = first(510, (10, 10));
+        assert_eq!(114, res);
+    }
+
+    #[test]
+    fn test2() {
+        let res = second(510, (10, 10));
+        assert_eq!(45, res);
+    }
+
+    #[test]
+    fn test_erosion() {
+        let cave = Cave::new(510, (10, 10));
+        assert_eq!(1805, cave.erosion((1, 1)));
+        assert_eq!(510, cave.erosion((10, 10)));
+        assert_eq!(8415, cave.erosion((0, 1)));
+        assert_eq!(17317, cave.erosion((1, 0)));
+        // well past the target, with nothing precomputed around it
+        let far = Cave::new(510, (10, 10));
+        assert_eq!(cave.erosion((2000, 3)), far.erosion((2000, 3)));
+        assert_eq!(RegionType::Narrow, cave.region_type((1, 1)));
+    }
+
+    #[test]
+    fn test_route() {
+        let route = Route::find(510, (10, 10));
+        let expected = "M=.|=.|.|=.|
+tNnnG|||..|.
+.==|g...||=.
+=.|.g..|.==.
+=|..g=...=.|
+=||.g.=||=|=
+|.=.g==|||..
+|..=g||=.|==
+.=..gg=..=|.
+.====g=|||=|
+.===|g|===X=
+=|||.gg|==g.
+=.=|=.ggggg|
+||=|=...|==.
+";
+        assert_eq!(expected, route.render(None));
+        assert_eq!(45, route.minutes());
+        assert_eq!(Some(&45), route.times().last());
+        let last = route.path.states.last().unwrap();
+        assert_eq!(((10, 10), Tool::Torch), (last.pos, last.tool));
+
+        // switching to neither takes from minute 2 to 9
+        let replay = route.render(Some(8));
+        assert_eq!("M=.|=.|.|=.|\nt@=|=|||..|.\n", &replay[..26]);
+        assert_eq!(replay, route.render(Some(2)));
+        assert_eq!("t@", &route.render(Some(9))[13..15]);
+        assert_eq!("tN@", &route.render(Some(10))[13..16]);
+
+        let image = route.render_ppm(None, 2);
+        let header = b"P6\n24 28\n255\n";
+        assert_eq!(header, &image[..header.len()]);
+        assert_eq!(header.len() + 24 * 28 * 3, image.len());
+        assert_eq!(
+            [40, 200, 80, 40, 200, 80, 64, 112],
+            image[header.len()..][..8]
+        );
+    }
+}
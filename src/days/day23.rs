@@ -0,0 +1,197 @@
+use crate::*;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+fn first(input: &[&str]) -> Result<usize, ParseErrors> {
+    let bots: Vec<Nanobot> = parse_lines(input)?;
+    let strongest = bots.iter().max_by_key(|b| b.r).ok_or_else(no_bots)?;
+    Ok(bots
+        .iter()
+        .filter(|b| distance(&strongest.pos, &b.pos) <= strongest.r)
+        .count())
+}
+
+fn second(input: &[&str]) -> Result<i64, ParseErrors> {
+    let bots: Vec<Nanobot> = parse_lines(input)?;
+
+    let max_coord = bots
+        .iter()
+        .map(|b| b.pos.0.abs().max(b.pos.1.abs()).max(b.pos.2.abs()) + b.r)
+        .max()
+        .ok_or_else(no_bots)?;
+    let mut size = 1;
+    while size < max_coord {
+        size *= 2;
+    }
+
+    // the best cube is split until it is a single point, its children always leave the queue
+    // something to pop
+    let mut queue = BinaryHeap::new();
+    let mut cube = Cube::new((-size, -size, -size), size * 2, &bots);
+    while cube.size.0 > 1 {
+        for child in cube.split() {
+            queue.push(Cube::new(child.0, child.1, &bots));
+        }
+        cube = match queue.pop() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    Ok(cube.distance.0)
+}
+
+fn no_bots() -> ParseErrors {
+    ParseError::whole("at least one nanobot").into()
+}
+
+type Pos = (i64, i64, i64);
+
+fn distance(a: &Pos, b: &Pos) -> i64 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs() + (a.2 - b.2).abs()
+}
+
+struct Nanobot {
+    pos: Pos,
+    r: i64,
+}
+
+lazy_static! {
+    static ref RE: Regex = Regex::new(r"^pos=<(-?\d+),(-?\d+),(-?\d+)>, r=(\d+)$").unwrap();
+}
+
+impl FromLine for Nanobot {
+    fn from_line(line: &str) -> Result<Nanobot, ParseError> {
+        let cap = RE
+            .captures(line)
+            .ok_or_else(|| ParseError::no_match(line, &RE))?;
+        let x = parse_capture(&cap, 1, "x")?;
+        let y = parse_capture(&cap, 2, "y")?;
+        let z = parse_capture(&cap, 3, "z")?;
+        let r = parse_capture(&cap, 4, "r")?;
+        Ok(Nanobot { pos: (x, y, z), r })
+    }
+}
+
+impl Nanobot {
+    fn reaches(&self, min: &Pos, size: i64) -> bool {
+        let axis = |p: i64, lo: i64| {
+            let hi = lo + size - 1;
+            if p < lo {
+                lo - p
+            } else if p > hi {
+                p - hi
+            } else {
+                0
+            }
+        };
+        let d = axis(self.pos.0, min.0) + axis(self.pos.1, min.1) + axis(self.pos.2, min.2);
+        d <= self.r
+    }
+}
+
+// a cube of side `size` starting at `min`, ordered so that the heap pops first the cubes
+// in range of the most bots, then the ones closest to the origin, then the smallest
+#[derive(Eq, PartialEq, Ord, PartialOrd)]
+struct Cube {
+    in_range: usize,
+    distance: Reverse<i64>,
+    size: Reverse<i64>,
+    min: Pos,
+}
+
+impl Cube {
+    fn new(min: Pos, size: i64, bots: &[Nanobot]) -> Cube {
+        let in_range = bots.iter().filter(|b| b.reaches(&min, size)).count();
+        let axis = |lo: i64| {
+            if lo > 0 {
+                lo
+            } else if lo + size - 1 < 0 {
+                -(lo + size - 1)
+            } else {
+                0
+            }
+        };
+        let distance = axis(min.0) + axis(min.1) + axis(min.2);
+        Cube {
+            in_range,
+            distance: Reverse(distance),
+            size: Reverse(size),
+            min,
+        }
+    }
+
+    fn split(&self) -> Vec<(Pos, i64)> {
+        let half = self.size.0 / 2;
+        let mut res = Vec::with_capacity(8);
+        for dx in 0..2 {
+            for dy in 0..2 {
+                for dz in 0..2 {
+                    let min = (
+                        self.min.0 + dx * half,
+                        self.min.1 + dy * half,
+                        self.min.2 + dz * half,
+                    );
+                    res.push((min, half));
+                }
+            }
+        }
+        res
+    }
+}
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let input = [
+            "pos=<0,0,0>, r=4",
+            "pos=<1,0,0>, r=1",
+            "pos=<4,0,0>, r=3",
+            "pos=<0,2,0>, r=1",
+            "pos=<0,5,0>, r=3",
+            "pos=<0,0,3>, r=1",
+            "pos=<1,1,1>, r=1",
+            "pos=<1,1,2>, r=1",
+            "pos=<1,3,1>, r=1",
+        ];
+        assert_eq!(Ok(7), first(&input));
+    }
+
+    #[test]
+    fn test2() {
+        let input = [
+            "pos=<10,12,12>, r=2",
+            "pos=<12,14,12>, r=2",
+            "pos=<16,12,12>, r=4",
+            "pos=<14,14,14>, r=6",
+            "pos=<50,50,50>, r=200",
+            "pos=<10,10,10>, r=5",
+        ];
+        assert_eq!(Ok(36), second(&input));
+    }
+
+    #[test]
+    fn test_empty() {
+        let expected = "expected at least one nanobot";
+        assert_eq!(expected, first(&[]).err().unwrap().to_string());
+        assert_eq!(expected, second(&[]).err().unwrap().to_string());
+    }
+}
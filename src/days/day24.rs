@@ -0,0 +1,246 @@
+use crate::*;
+
+use std::cmp::Reverse;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+fn first(input: &[&str]) -> Result<u32, SolveError> {
+    let groups = parse(input)?;
+    let (_, units) =
+        fight(groups).ok_or_else(|| SolveError::unsolvable("the fight ends in a stalemate"))?;
+    Ok(units)
+}
+
+fn second(input: &[&str]) -> Result<u32, ParseErrors> {
+    let groups = parse(input)?;
+
+    let mut boost = 1;
+    loop {
+        let boosted = groups
+            .iter()
+            .cloned()
+            .map(|mut g| {
+                if g.army == Army::ImmuneSystem {
+                    g.attack += boost;
+                }
+                g
+            })
+            .collect();
+
+        if let Some((Army::ImmuneSystem, units)) = fight(boosted) {
+            return Ok(units);
+        }
+        boost += 1;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Army {
+    ImmuneSystem,
+    Infection,
+}
+
+#[derive(Clone, Debug)]
+struct Group {
+    army: Army,
+    units: u32,
+    hp: u32,
+    attack: u32,
+    attack_type: String,
+    initiative: u32,
+    weak: Vec<String>,
+    immune: Vec<String>,
+}
+
+impl Group {
+    fn power(&self) -> u32 {
+        self.units * self.attack
+    }
+
+    fn damage_to(&self, other: &Group) -> u32 {
+        if other.immune.contains(&self.attack_type) {
+            0
+        } else if other.weak.contains(&self.attack_type) {
+            self.power() * 2
+        } else {
+            self.power()
+        }
+    }
+}
+
+// returns the winning army and its remaining units, or None if the fight ends in a stalemate
+fn fight(mut groups: Vec<Group>) -> Option<(Army, u32)> {
+    loop {
+        let alive = |army| groups.iter().any(|g| g.army == army);
+        if !alive(Army::ImmuneSystem) || !alive(Army::Infection) {
+            let units = groups.iter().map(|g| g.units).sum();
+            return groups.first().map(|g| (g.army, units));
+        }
+
+        let mut order: Vec<usize> = (0..groups.len()).collect();
+        order.sort_by_key(|&i| Reverse((groups[i].power(), groups[i].initiative)));
+
+        let mut targets = vec![None; groups.len()];
+        let mut taken = vec![false; groups.len()];
+        for &attacker in order.iter() {
+            let a = &groups[attacker];
+            let target = (0..groups.len())
+                .filter(|&t| !taken[t] && groups[t].army != a.army)
+                .map(|t| (a.damage_to(&groups[t]), t))
+                .filter(|&(damage, _)| damage > 0)
+                .max_by_key(|&(damage, t)| (damage, groups[t].power(), groups[t].initiative));
+
+            if let Some((_, t)) = target {
+                taken[t] = true;
+                targets[attacker] = Some(t);
+            }
+        }
+
+        order.sort_by_key(|&i| Reverse(groups[i].initiative));
+
+        let mut killed = 0;
+        for attacker in order {
+            if groups[attacker].units == 0 {
+                continue;
+            }
+            if let Some(t) = targets[attacker] {
+                let damage = groups[attacker].damage_to(&groups[t]);
+                let dead = (damage / groups[t].hp).min(groups[t].units);
+                groups[t].units -= dead;
+                killed += dead;
+            }
+        }
+
+        if killed == 0 {
+            return None;
+        }
+
+        groups.retain(|g| g.units > 0);
+    }
+}
+
+lazy_static! {
+    static ref GROUP_RE: Regex = Regex::new(
+        r"^(\d+) units each with (\d+) hit points (?:\(([^)]*)\) )?with an attack that does (\d+) (\w+) damage at initiative (\d+)$"
+    )
+    .unwrap();
+}
+
+fn parse_group(line: &str, army: Army) -> Result<Group, ParseError> {
+    let cap = GROUP_RE
+        .captures(line)
+        .ok_or_else(|| ParseError::no_match(line, &GROUP_RE))?;
+
+    let mut weak = Vec::new();
+    let mut immune = Vec::new();
+    if let Some(modifiers) = cap.get(3) {
+        for modifier in modifiers.as_str().split("; ") {
+            let (list, types) = if let Some(types) = modifier.strip_prefix("weak to ") {
+                (&mut weak, types)
+            } else if let Some(types) = modifier.strip_prefix("immune to ") {
+                (&mut immune, types)
+            } else {
+                let column = modifiers.start() + 1;
+                return Err(ParseError::new(column, modifier, "weak to or immune to"));
+            };
+            list.extend(types.split(", ").map(String::from));
+        }
+    }
+
+    Ok(Group {
+        army,
+        units: parse_capture(&cap, 1, "units")?,
+        hp: parse_capture(&cap, 2, "hit points")?,
+        attack: parse_capture(&cap, 4, "attack")?,
+        attack_type: parse_capture(&cap, 5, "attack type")?,
+        initiative: parse_capture(&cap, 6, "initiative")?,
+        weak,
+        immune,
+    })
+}
+
+fn parse(input: &[&str]) -> Result<Vec<Group>, ParseErrors> {
+    let mut groups = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut army = None;
+    for (i, &line) in input.iter().enumerate() {
+        match line {
+            "" => {}
+            "Immune System:" => army = Some(Army::ImmuneSystem),
+            "Infection:" => army = Some(Army::Infection),
+            _ => {
+                let res = match army {
+                    Some(army) => parse_group(line, army),
+                    None => Err(ParseError::new(1, line, "Immune System: or Infection:")),
+                };
+                match res {
+                    Ok(group) => groups.push(group),
+                    Err(e) => errors.push(e.at_line(i + 1)),
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(ParseErrors(errors));
+    }
+    for army in [Army::ImmuneSystem, Army::Infection] {
+        if !groups.iter().any(|g| g.army == army) {
+            let expected = "groups in both the Immune System and the Infection";
+            return Err(ParseError::whole(expected).into());
+        }
+    }
+    Ok(groups)
+}
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INPUT: [&str; 7] = [
+        "Immune System:",
+        "17 units each with 5390 hit points (weak to radiation, bludgeoning) with an attack that does 4507 fire damage at initiative 2",
+        "989 units each with 1274 hit points (immune to fire; weak to bludgeoning, slashing) with an attack that does 25 slashing damage at initiative 3",
+        "",
+        "Infection:",
+        "801 units each with 4706 hit points (weak to radiation) with an attack that does 116 bludgeoning damage at initiative 1",
+        "4485 units each with 2961 hit points (immune to radiation; weak to fire, cold) with an attack that does 12 slashing damage at initiative 4",
+    ];
+
+    #[test]
+    fn test1() {
+        assert_eq!(Ok(5216), first(&INPUT));
+    }
+
+    #[test]
+    fn test2() {
+        assert_eq!(Ok(51), second(&INPUT));
+    }
+
+    #[test]
+    fn test_parse() {
+        let groups = parse(&INPUT).unwrap();
+        assert_eq!(4, groups.len());
+        assert_eq!(vec!["fire"], groups[1].immune);
+        assert_eq!(vec!["bludgeoning", "slashing"], groups[1].weak);
+        assert_eq!(Army::Infection, groups[2].army);
+
+        let expected = "expected groups in both the Immune System and the Infection";
+        assert_eq!(expected, first(&[]).err().unwrap().to_string());
+        assert_eq!(expected, second(&INPUT[..3]).err().unwrap().to_string());
+    }
+}
@@ -0,0 +1,140 @@
+use crate::*;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+fn first(input: &[&str]) -> Result<usize, ParseErrors> {
+    let points: Vec<Point> = parse_lines(input)?;
+
+    let mut parents: Vec<usize> = (0..points.len()).collect();
+
+    fn root(parents: &mut [usize], i: usize) -> usize {
+        let mut r = i;
+        while parents[r] != r {
+            r = parents[r];
+        }
+        parents[i] = r;
+        r
+    }
+
+    for i in 0..points.len() {
+        for j in i + 1..points.len() {
+            if points[i].distance(&points[j]) <= 3 {
+                let (ri, rj) = (root(&mut parents, i), root(&mut parents, j));
+                parents[ri] = rj;
+            }
+        }
+    }
+
+    Ok((0..points.len())
+        .filter(|&i| root(&mut parents, i) == i)
+        .count())
+}
+
+struct Point([i32; 4]);
+
+impl Point {
+    fn distance(&self, other: &Point) -> i32 {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum()
+    }
+}
+
+lazy_static! {
+    static ref RE: Regex = Regex::new(r"^\s*(-?\d+),(-?\d+),(-?\d+),(-?\d+)$").unwrap();
+}
+
+impl FromLine for Point {
+    fn from_line(line: &str) -> Result<Point, ParseError> {
+        let cap = RE
+            .captures(line)
+            .ok_or_else(|| ParseError::no_match(line, &RE))?;
+        Ok(Point([
+            parse_capture(&cap, 1, "x")?,
+            parse_capture(&cap, 2, "y")?,
+            parse_capture(&cap, 3, "z")?,
+            parse_capture(&cap, 4, "t")?,
+        ]))
+    }
+}
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, _input: &str) -> Result<String, SolveError> {
+        // the last puzzle of the year has no second part
+        Ok(String::from("-"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let input = [
+            " 0,0,0,0", " 3,0,0,0", " 0,3,0,0", " 0,0,3,0", " 0,0,0,3", " 0,0,0,6", " 9,0,0,0",
+            "12,0,0,0",
+        ];
+        assert_eq!(Ok(2), first(&input));
+    }
+
+    #[test]
+    fn test2() {
+        let input = [
+            "-1,2,2,0",
+            "0,0,2,-2",
+            "0,0,0,-2",
+            "-1,2,0,0",
+            "-2,-2,-2,2",
+            "3,0,2,-1",
+            "-1,3,2,2",
+            "-1,0,-1,0",
+            "0,2,1,-2",
+            "3,0,0,0",
+        ];
+        assert_eq!(Ok(4), first(&input));
+    }
+
+    #[test]
+    fn test3() {
+        let input = [
+            "1,-1,0,1",
+            "2,0,-1,0",
+            "3,2,-1,0",
+            "0,0,3,1",
+            "0,0,-1,-1",
+            "2,3,-2,0",
+            "-2,2,0,0",
+            "2,-2,0,-1",
+            "1,-1,0,-1",
+            "3,2,0,2",
+        ];
+        assert_eq!(Ok(3), first(&input));
+    }
+
+    #[test]
+    fn test4() {
+        let input = [
+            "1,-1,-1,-2",
+            "-2,-2,0,1",
+            "0,2,1,3",
+            "-2,3,-2,1",
+            "0,2,3,-2",
+            "-1,-1,1,-2",
+            "0,-2,-1,0",
+            "-2,2,3,-1",
+            "1,2,2,0",
+            "-1,-2,0,-2",
+        ];
+        assert_eq!(Ok(8), first(&input));
+    }
+}
@@ -0,0 +1,40 @@
+use crate::*;
+
+fn first(input: &[&str]) -> Result<u32, ParseErrors> {
+    let input = parse(input)?;
+    Ok(input.len() as u32)
+}
+
+fn second(input: &[&str]) -> Result<u32, ParseErrors> {
+    let input = parse(input)?;
+    Ok(input.iter().sum())
+}
+
+fn parse(input: &[&str]) -> Result<Vec<u32>, ParseErrors> {
+    parse_lines_with(input, |line| parse_value(line, "a number"))
+}
+
+pub struct DayNN;
+
+impl Solution for DayNN {
+    fn first(&self, input: &str) -> Result<String, SolveError> {
+        Ok(first(&lines(input))?.to_string())
+    }
+
+    fn second(&self, input: &str) -> Result<String, SolveError> {
+        Ok(second(&lines(input))?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test() {
+        assert_eq!(Ok(3), first(&["1", "2", "3"]));
+        assert_eq!(Ok(6), second(&["1", "2", "3"]));
+        assert!(first(&["1", "a"]).is_err());
+        assert_eq!(Ok(String::from("6")), DayNN.second("1\n2\n3"));
+    }
+}
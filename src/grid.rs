@@ -0,0 +1,457 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::*;
+
+// y grows downwards, and points sort in reading order: top to bottom, then left to right
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub fn new(x: isize, y: isize) -> Point {
+        Point { x, y }
+    }
+
+    pub fn offset(&self, dx: isize, dy: isize) -> Point {
+        Point::new(self.x + dx, self.y + dy)
+    }
+
+    // up, left, right, down, which is reading order
+    pub fn neighbours4(&self) -> [Point; 4] {
+        [
+            self.offset(0, -1),
+            self.offset(-1, 0),
+            self.offset(1, 0),
+            self.offset(0, 1),
+        ]
+    }
+
+    // in reading order too
+    pub fn neighbours8(&self) -> [Point; 8] {
+        [
+            self.offset(-1, -1),
+            self.offset(0, -1),
+            self.offset(1, -1),
+            self.offset(-1, 0),
+            self.offset(1, 0),
+            self.offset(-1, 1),
+            self.offset(0, 1),
+            self.offset(1, 1),
+        ]
+    }
+
+    pub fn manhattan(&self, other: &Point) -> usize {
+        ((self.x - other.x).abs() + (self.y - other.y).abs()) as usize
+    }
+}
+
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
+}
+
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Debug for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+// one character per cell, reporting every invalid one
+fn parse_cells<T, F>(input: &[&str], f: F) -> Result<Vec<(Point, T)>, ParseErrors>
+where
+    F: Fn(char) -> Result<T, ParseError>,
+{
+    let mut res = Vec::new();
+    let mut errors = Vec::new();
+
+    for (y, line) in input.iter().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            match f(c) {
+                Ok(v) => res.push((Point::new(x as isize, y as isize), v)),
+                Err(e) => errors.push(e.at_line(y + 1).at_column(x + 1)),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(res)
+    } else {
+        Err(ParseErrors(errors))
+    }
+}
+
+// a rectangle of cells with its top left corner at (0, 0)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    pub fn from_fn<F>(width: usize, height: usize, mut f: F) -> Grid<T>
+    where
+        F: FnMut(Point) -> T,
+    {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Point::new(x as isize, y as isize)))
+            .map(&mut f)
+            .collect();
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    // every line must have the same length
+    pub fn parse_with<F>(input: &[&str], f: F) -> Result<Grid<T>, ParseErrors>
+    where
+        F: Fn(char) -> Result<T, ParseError>,
+    {
+        let width = input.first().map_or(0, |l| l.chars().count());
+        let ragged: Vec<ParseError> = input
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.chars().count() != width)
+            .map(|(y, l)| ParseError::new(1, l, &format!("{} cells", width)).at_line(y + 1))
+            .collect();
+        if !ragged.is_empty() {
+            return Err(ParseErrors(ragged));
+        }
+
+        let cells = parse_cells(input, f)?;
+        Ok(Grid {
+            width,
+            height: input.len(),
+            cells: cells.into_iter().map(|(_, v)| v).collect(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height
+    }
+
+    fn index(&self, p: &Point) -> Option<usize> {
+        if self.contains(p) {
+            Some(p.y as usize * self.width + p.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, p: &Point) -> Option<&T> {
+        self.index(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: &Point) -> Option<&mut T> {
+        self.index(p).map(move |i| &mut self.cells[i])
+    }
+
+    // false, leaving the grid untouched, when the point is outside of it
+    pub fn set(&mut self, p: &Point, value: T) -> bool {
+        match self.get_mut(p) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // in reading order
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| Point::new((i % width) as isize, (i / width) as isize))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    // the neighbours inside the grid
+    pub fn neighbours4(&self, p: &Point) -> impl Iterator<Item = Point> + '_ {
+        let n = p.neighbours4();
+        (0..4).map(move |i| n[i]).filter(move |q| self.contains(q))
+    }
+
+    pub fn neighbours8(&self, p: &Point) -> impl Iterator<Item = Point> + '_ {
+        let n = p.neighbours8();
+        (0..8).map(move |i| n[i]).filter(move |q| self.contains(q))
+    }
+
+    pub fn map<U, F>(&self, mut f: F) -> Grid<U>
+    where
+        F: FnMut(Point, &T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.iter().map(|(p, v)| f(p, v)).collect(),
+        }
+    }
+
+    // one line per row, each ending with a newline
+    pub fn render_with<F>(&self, f: F) -> String
+    where
+        F: Fn(&T) -> char,
+    {
+        let mut res = String::with_capacity((self.width + 1) * self.height);
+        for row in self.cells.chunks(self.width.max(1)) {
+            res.extend(row.iter().map(&f));
+            res.push('\n');
+        }
+        res
+    }
+}
+
+// the occupied cells of an unbounded plane
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> SparseGrid<T> {
+        SparseGrid {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    // the function returns None for the cells left empty
+    pub fn parse_with<F>(input: &[&str], f: F) -> Result<SparseGrid<T>, ParseErrors>
+    where
+        F: Fn(char) -> Result<Option<T>, ParseError>,
+    {
+        let cells = parse_cells(input, f)?;
+        Ok(SparseGrid {
+            cells: cells
+                .into_iter()
+                .filter_map(|(p, v)| v.map(|v| (p, v)))
+                .collect(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        self.cells.contains_key(p)
+    }
+
+    pub fn get(&self, p: &Point) -> Option<&T> {
+        self.cells.get(p)
+    }
+
+    pub fn get_mut(&mut self, p: &Point) -> Option<&mut T> {
+        self.cells.get_mut(p)
+    }
+
+    pub fn insert(&mut self, p: Point, value: T) -> Option<T> {
+        self.cells.insert(p, value)
+    }
+
+    pub fn remove(&mut self, p: &Point) -> Option<T> {
+        self.cells.remove(p)
+    }
+
+    // in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&Point, &T)> {
+        self.cells.iter()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.values()
+    }
+
+    // the occupied points, in reading order
+    pub fn points(&self) -> Vec<Point> {
+        let mut res: Vec<Point> = self.cells.keys().copied().collect();
+        res.sort_unstable();
+        res
+    }
+
+    // the top left and bottom right corners of the occupied cells
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let mut points = self.cells.keys();
+        let first = *points.next()?;
+        Some(points.fold((first, first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        }))
+    }
+
+    // the bounding rectangle, with `empty` where nothing is
+    pub fn render_with<F>(&self, empty: char, f: F) -> String
+    where
+        F: Fn(&T) -> char,
+    {
+        let (min, max) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return String::new(),
+        };
+        let mut res = String::new();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                res.push(self.get(&Point::new(x, y)).map_or(empty, &f));
+            }
+            res.push('\n');
+        }
+        res
+    }
+}
+
+impl<T> FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> SparseGrid<T> {
+        SparseGrid {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_point() {
+        let mut points = vec![Point::new(2, 0), Point::new(0, 1), Point::new(1, 0)];
+        points.sort();
+        assert_eq!(
+            vec![Point::new(1, 0), Point::new(2, 0), Point::new(0, 1)],
+            points
+        );
+
+        let p = Point::new(3, -2);
+        let mut n4 = p.neighbours4().to_vec();
+        n4.sort();
+        assert_eq!(p.neighbours4().to_vec(), n4);
+        let mut n8 = p.neighbours8().to_vec();
+        n8.sort();
+        assert_eq!(p.neighbours8().to_vec(), n8);
+        assert_eq!(7, p.manhattan(&Point::new(-1, 1)));
+        assert_eq!("(3, -2)", format!("{:?}", p));
+    }
+
+    fn digit(c: char) -> Result<u32, ParseError> {
+        c.to_digit(10)
+            .ok_or_else(|| ParseError::new(1, &c.to_string(), "a digit"))
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut grid = Grid::parse_with(&["123", "456"], digit).unwrap();
+        assert_eq!((3, 2), (grid.width(), grid.height()));
+        assert_eq!(Some(&6), grid.get(&Point::new(2, 1)));
+        assert_eq!(None, grid.get(&Point::new(3, 0)));
+        assert_eq!(None, grid.get(&Point::new(0, -1)));
+
+        let corner: Vec<Point> = grid.neighbours8(&Point::new(0, 0)).collect();
+        assert_eq!(
+            vec![Point::new(1, 0), Point::new(0, 1), Point::new(1, 1)],
+            corner
+        );
+        let sum: u32 = grid
+            .neighbours4(&Point::new(1, 1))
+            .map(|p| grid.get(&p).unwrap())
+            .sum();
+        assert_eq!(2 + 4 + 6, sum);
+
+        assert!(grid.set(&Point::new(1, 0), 0));
+        assert!(!grid.set(&Point::new(1, 2), 0));
+        let doubled = grid.map(|_, v| v * 2);
+        assert_eq!(
+            vec![2, 0, 6, 8, 10, 12],
+            doubled.values().copied().collect::<Vec<u32>>()
+        );
+        assert_eq!(
+            "103\n456\n",
+            grid.render_with(|v| (b'0' + *v as u8) as char)
+        );
+
+        let products = Grid::from_fn(3, 2, |p| p.x * p.y);
+        assert_eq!(Some(&2), products.get(&Point::new(2, 1)));
+    }
+
+    #[test]
+    fn test_grid_errors() {
+        let errors = Grid::parse_with(&["12", "3x", "y4"], digit).err().unwrap();
+        assert_eq!(
+            "line 2, column 2: expected a digit, found \"x\"\n\
+             line 3, column 1: expected a digit, found \"y\"",
+            errors.to_string()
+        );
+        let errors = Grid::parse_with(&["12", "345"], digit).err().unwrap();
+        assert_eq!(
+            "line 2, column 1: expected 2 cells, found \"345\"",
+            errors.to_string()
+        );
+    }
+
+    #[test]
+    fn test_sparse() {
+        let input = ["#..", "..#", ".E."];
+        let wall = |c| match c {
+            '#' => Ok(Some(true)),
+            'E' => Ok(Some(false)),
+            '.' => Ok(None),
+            c => Err(ParseError::new(1, &c.to_string(), "one of '#', 'E', '.'")),
+        };
+        let mut grid = SparseGrid::parse_with(&input, wall).unwrap();
+        assert_eq!(3, grid.len());
+        assert_eq!(
+            vec![Point::new(0, 0), Point::new(2, 1), Point::new(1, 2)],
+            grid.points()
+        );
+        assert_eq!(Some(false), grid.remove(&Point::new(1, 2)));
+        grid.insert(Point::new(-1, 3), false);
+        assert_eq!(Some((Point::new(-1, 0), Point::new(2, 3))), grid.bounds());
+        let render = |&w: &bool| if w { '#' } else { 'E' };
+        assert_eq!(".#..\n...#\n....\nE...\n", grid.render_with('.', render));
+        assert_eq!("", SparseGrid::<bool>::default().render_with('.', render));
+    }
+}
@@ -0,0 +1,123 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    Day(u8),
+    Path(PathBuf),
+    Stdin,
+}
+
+impl Source {
+    pub fn from_arg(arg: &str) -> Source {
+        if arg == "-" {
+            Source::Stdin
+        } else {
+            Source::Path(PathBuf::from(arg))
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Day(day) => write!(f, "{}", day_path(*day).display()),
+            Source::Path(path) => write!(f, "{}", path.display()),
+            Source::Stdin => write!(f, "<stdin>"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Read { path: PathBuf, source: io::Error },
+    Stdin(io::Error),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Read { path, source } => {
+                write!(f, "failed to read {}: {}", path.display(), source)
+            }
+            InputError::Stdin(e) => write!(f, "failed to read stdin: {}", e),
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InputError::Read { source, .. } => Some(source),
+            InputError::Stdin(e) => Some(e),
+        }
+    }
+}
+
+pub fn input_dir() -> PathBuf {
+    env::var_os(INPUT_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("input"))
+}
+
+pub fn day_path(day: u8) -> PathBuf {
+    input_dir().join(format!("{:02}.txt", day))
+}
+
+pub fn load(source: &Source) -> Result<String, InputError> {
+    let raw = match source {
+        Source::Day(day) => read_path(day_path(*day))?,
+        Source::Path(path) => read_path(path.clone())?,
+        Source::Stdin => {
+            let mut raw = String::new();
+            io::stdin()
+                .read_to_string(&mut raw)
+                .map_err(InputError::Stdin)?;
+            raw
+        }
+    };
+    Ok(normalize(&raw))
+}
+
+fn read_path(path: PathBuf) -> Result<String, InputError> {
+    fs::read_to_string(&path).map_err(|source| InputError::Read { path, source })
+}
+
+pub fn normalize(raw: &str) -> String {
+    let lines: Vec<&str> = raw.trim_end().lines().map(str::trim_end).collect();
+    let mut res = lines.join("\n");
+    res.push('\n');
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!("a\n  b\nc\n", normalize("a  \r\n  b\r\nc\r\n\r\n"));
+        assert_eq!("a\nb\n", normalize("a\nb"));
+    }
+
+    #[test]
+    fn test_source() {
+        assert_eq!(Source::Stdin, Source::from_arg("-"));
+        assert_eq!(
+            Source::Path(PathBuf::from("input/01.txt")),
+            Source::from_arg("input/01.txt")
+        );
+    }
+
+    #[test]
+    fn test_missing() {
+        let res = load(&Source::Path(PathBuf::from("input/missing.txt")));
+        assert!(matches!(res, Err(InputError::Read { .. })));
+    }
+}
@@ -1,24 +1,59 @@
+use std::error::Error;
+use std::fmt;
+
+pub mod days;
+pub mod grid;
+pub mod input;
 pub mod machine;
+pub mod parse;
+pub mod search;
+
+pub use crate::parse::{
+    parse_capture, parse_lines, parse_lines_with, parse_value, FromLine, ParseError, ParseErrors,
+};
+
+// why a day has no answer: its input is malformed, or the input is well formed but the
+// solution can't find an answer from it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    Parse(ParseErrors),
+    Unsolvable(String),
+}
+
+impl SolveError {
+    pub fn unsolvable(reason: &str) -> SolveError {
+        SolveError::Unsolvable(String::from(reason))
+    }
+}
 
-use std::fs;
-use std::str::FromStr;
+impl From<ParseErrors> for SolveError {
+    fn from(e: ParseErrors) -> SolveError {
+        SolveError::Parse(e)
+    }
+}
+
+impl From<ParseError> for SolveError {
+    fn from(e: ParseError) -> SolveError {
+        SolveError::Parse(e.into())
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Parse(e) => write!(f, "{}", e),
+            SolveError::Unsolvable(reason) => write!(f, "{}", reason),
+        }
+    }
+}
 
-use regex::Captures;
+impl Error for SolveError {}
 
-pub fn read_file(day: &str) -> String {
-    let path = format!("input/{}.txt", day);
-    fs::read_to_string(path).unwrap()
+pub trait Solution {
+    fn first(&self, input: &str) -> Result<String, SolveError>;
+    fn second(&self, input: &str) -> Result<String, SolveError>;
 }
 
-pub fn parse_capture<F>(cap: &Captures, idx: usize, name: &str) -> Result<F, String>
-where
-    F: FromStr,
-    <F as std::str::FromStr>::Err: std::fmt::Debug,
-{
-    let ma = cap
-        .get(idx)
-        .ok_or_else(|| format!("{} not found", name))?
-        .as_str();
-    ma.parse::<F>()
-        .map_err(|e| format!("failed to parse {}, {:?}", name, e))
+pub fn lines(input: &str) -> Vec<&str> {
+    input.trim_end().lines().map(str::trim_end).collect()
 }
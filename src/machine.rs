@@ -1,86 +1,385 @@
+use std::collections::HashSet;
+use std::fmt;
+
 use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::*;
 
-#[derive(PartialEq, Debug)]
+pub mod assembler;
+pub mod bigint;
+pub mod cfg;
+pub mod compiled;
+pub mod cycle;
+pub mod debugger;
+pub mod decompiler;
+pub mod discovery;
+pub mod optimizer;
+pub mod registry;
+pub mod snapshot;
+pub mod symbolic;
+pub mod trace;
+pub mod word;
+
+pub use bigint::BigInt;
+use compiled::Compiled;
+use debugger::Watchpoint;
+use optimizer::Idiom;
+use registry::{Effect, Ext, Io, Registry};
+use trace::{Profile, Tracer};
+pub use word::{Overflow, Word};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Engine {
+    Interpreter,
+    Compiled,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Fault {
+    UnknownInstruction(i32),
+    InvalidRegister(i32, i32),
+    Overflow(i32),
+    Trap(i32),
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::UnknownInstruction(ip) => write!(f, "unknown instruction at {}", ip),
+            Fault::InvalidRegister(ip, r) => write!(f, "invalid register {} at {}", r, ip),
+            Fault::Overflow(ip) => write!(f, "overflow at {}", ip),
+            Fault::Trap(ip) => write!(f, "trap at {}", ip),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum MachineState {
     Active,
     Halted,
+    Faulted(Fault),
 }
 
-pub struct Machine<const N: usize> {
-    pub registers: Registers<N>,
+pub struct Machine<const N: usize, W: Word = i32> {
+    pub registers: Registers<N, W>,
     pub ip_reg: u8,
     program: Vec<OpCode>,
+    registry: Registry<W>,
+    pub io: Io<W>,
+    overflow: Overflow,
+    steps: u64,
+    accelerators: Vec<Option<Idiom>>,
+    compiled: Option<Compiled<N, W>>,
+    breakpoints: HashSet<i32>,
+    // where the last breakpoint was reported, as (steps, ip), so that resuming goes past it
+    paused: Option<(u64, i32)>,
+    watchpoints: Vec<Watchpoint>,
+    tracer: Option<Tracer>,
+    profile: Option<Profile>,
     pub state: MachineState,
 }
 
-impl<const N: usize> Machine<N> {
-    pub fn new(ip_reg: u8, program: Vec<OpCode>, registers: Registers<N>) -> Machine<N> {
+impl<const N: usize, W: Word> Machine<N, W> {
+    pub fn new(ip_reg: u8, program: Vec<OpCode>, registers: Registers<N, W>) -> Machine<N, W> {
         Machine {
             registers,
             ip_reg,
             program,
+            registry: Registry::default(),
+            io: Io::default(),
+            overflow: Overflow::Wrap,
+            steps: 0,
+            accelerators: Vec::new(),
+            compiled: None,
+            breakpoints: HashSet::new(),
+            paused: None,
+            watchpoints: Vec::new(),
+            tracer: None,
+            profile: None,
             state: MachineState::Active,
         }
     }
 
+    pub fn program(&self) -> &[OpCode] {
+        &self.program
+    }
+
     pub fn curr_ip(&self) -> i32 {
         let ip_reg = &(self.ip_reg as i32);
-        self.registers.get(ip_reg)
+        self.registers.get(ip_reg).to_i32()
     }
 
-    pub fn execute_step(&mut self) {
+    // executed instructions, including the ones of accelerated loops
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+        if self.compiled.is_some() {
+            self.set_engine(Engine::Compiled);
+        }
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    // replaces the loops matching a known idiom with their native equivalent
+    pub fn optimize(&mut self) {
+        self.accelerators = optimizer::detect(&self.program, self.ip_reg, N);
+    }
+
+    // replaces the loops the registry has instructions for, see `optimizer::lower`
+    pub fn lower(&mut self) {
+        self.program = optimizer::lower(&self.program, self.ip_reg, N, &self.registry);
+        if !self.accelerators.is_empty() {
+            self.optimize();
+        }
+        if self.compiled.is_some() {
+            self.set_engine(Engine::Compiled);
+        }
+    }
+
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.compiled = match engine {
+            Engine::Interpreter => None,
+            Engine::Compiled => Some(Compiled::new(&self.program, self.overflow)),
+        };
+    }
+
+    pub fn engine(&self) -> Engine {
+        match self.compiled {
+            Some(_) => Engine::Compiled,
+            None => Engine::Interpreter,
+        }
+    }
+
+    // an instruction that can't be executed faithfully, like one using a register the
+    // machine doesn't have
+    fn fault(&self, ip: i32, instruction: &OpCode) -> Option<Fault> {
+        if self.ip_reg as usize >= N {
+            return Some(Fault::InvalidRegister(ip, self.ip_reg as i32));
+        }
+        let unknown = match instruction {
+            OpCode::Nop => true,
+            OpCode::Ext(ext, ..) => self.registry.get(ext).is_none(),
+            _ => false,
+        };
+        if unknown {
+            return Some(Fault::UnknownInstruction(ip));
+        }
+        instruction
+            .invalid_register(N)
+            .map(|r| Fault::InvalidRegister(ip, r))
+    }
+
+    fn next_ip(&mut self) {
         let ip_reg = &(self.ip_reg as i32);
         let ip = self.registers.get(ip_reg);
+        self.registers.set(ip_reg, ip.wrapping_add(W::one()));
+    }
+
+    pub fn execute_step(&mut self) {
+        let ip_reg = &(self.ip_reg as i32);
+        let ip = self.curr_ip();
 
         if let Some(instruction) = self.program.get(ip as usize) {
-            let mut new_registers = instruction.apply(&self.registers);
-            new_registers.set(ip_reg, new_registers.get(ip_reg) + 1);
-            self.registers = new_registers;
+            if let Some(fault) = self.fault(ip, instruction) {
+                self.state = MachineState::Faulted(fault);
+                return;
+            }
+        }
+
+        let before = if self.observing() {
+            Some(self.registers.clone())
         } else {
-            self.state = MachineState::Halted;
+            None
+        };
+
+        if let Some(Some(idiom)) = self.accelerators.get(ip as usize) {
+            if let Some(executed) = idiom.apply(&mut self.registers, ip_reg) {
+                self.steps += executed;
+                if let Some(before) = before {
+                    self.observe(ip, None, &before);
+                }
+                return;
+            }
+        }
+
+        let instruction = match self.program.get(ip as usize) {
+            Some(&instruction) => instruction,
+            None => {
+                self.state = MachineState::Halted;
+                return;
+            }
+        };
+
+        if let OpCode::Ext(ext, a, b, c) = instruction {
+            let custom = self.registry.get(&ext).unwrap();
+            match custom.execute((a, b, c), &mut self.registers.inner, &mut self.io) {
+                Effect::Continue => {}
+                Effect::Halt => {
+                    self.state = MachineState::Halted;
+                    return;
+                }
+                Effect::Trap => {
+                    self.state = MachineState::Faulted(Fault::Trap(ip));
+                    return;
+                }
+            }
+        }
+
+        let done = match &self.compiled {
+            _ if matches!(instruction, OpCode::Ext(..)) => true,
+            Some(code) => code.exec(ip, &mut self.registers),
+            None => match instruction.apply_with(&self.registers, self.overflow) {
+                Some(registers) => {
+                    self.registers = registers;
+                    true
+                }
+                None => false,
+            },
+        };
+        if !done {
+            self.state = MachineState::Faulted(Fault::Overflow(ip));
+            return;
+        }
+
+        self.next_ip();
+        self.steps += 1;
+        if let Some(before) = before {
+            self.observe(ip, Some(instruction), &before);
         }
     }
 
     pub fn run(&mut self) {
-        while let MachineState::Active = self.state {
+        self.run_to(u64::MAX, &HashSet::new());
+    }
+
+    // runs until the program stops, the step counter reaches `limit`, or the ip reaches one
+    // of `stops` after the first instruction; custom instructions are left to the interpreter
+    fn run_to(&mut self, limit: u64, stops: &HashSet<i32>) {
+        if let Some(code) = &self.compiled {
+            let ip_reg = self.ip_reg as usize;
+            let valid = self.program.iter().enumerate().all(|(ip, op)| {
+                !matches!(op, OpCode::Ext(..)) && self.fault(ip as i32, op).is_none()
+            });
+            if ip_reg < N && valid && !self.observing() && self.state == MachineState::Active {
+                let stops: Vec<bool> = (0..self.program.len() as i32)
+                    .map(|ip| stops.contains(&ip))
+                    .collect();
+                self.state = code.run(
+                    ip_reg,
+                    &mut self.registers,
+                    &self.accelerators,
+                    &stops,
+                    &mut self.steps,
+                    limit,
+                );
+                return;
+            }
+        }
+
+        while self.state == MachineState::Active && self.steps < limit {
             self.execute_step();
+            if !stops.is_empty() && stops.contains(&self.curr_ip()) {
+                return;
+            }
         }
     }
 
-    pub fn parse(input: &[&str]) -> Machine<N> {
+    pub fn parse(input: &[&str]) -> Result<Machine<N, W>, ParseErrors> {
+        Machine::parse_with(input, None, &[])
+    }
+
+    // rejects unknown instructions and registers the machine doesn't have, instead of
+    // leaving them to fault at runtime
+    pub fn parse_strict(input: &[&str]) -> Result<Machine<N, W>, ParseErrors> {
+        Machine::parse_with(input, Some(N), &[])
+    }
+
+    // like `parse_strict`, also accepting the instructions of the registry
+    pub fn parse_extended(
+        input: &[&str],
+        registry: Registry<W>,
+    ) -> Result<Machine<N, W>, ParseErrors> {
+        let mut machine = Machine::parse_with(input, Some(N), &registry.extensions())?;
+        machine.registry = registry;
+        Ok(machine)
+    }
+
+    pub fn registry(&self) -> &Registry<W> {
+        &self.registry
+    }
+
+    fn parse_with(
+        input: &[&str],
+        registers: Option<usize>,
+        extensions: &[Ext],
+    ) -> Result<Machine<N, W>, ParseErrors> {
         let mut ip_reg = 0;
         let mut program = Vec::new();
+        let mut errors = Vec::new();
 
-        for line in input {
-            if line.starts_with('#') {
-                ip_reg = line.chars().last().unwrap().to_string().parse().unwrap();
-            } else if let Some(op) = OpCode::parse(line) {
-                program.push(op);
+        for (i, line) in input.iter().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+
+            let res = if let Some(cap) = IP_RE.captures(line) {
+                parse_capture(&cap, 1, "ip register").and_then(|r: u8| match registers {
+                    Some(n) if r as usize >= n => {
+                        Err(ParseError::new(5, &cap[1], &register_range(n)))
+                    }
+                    _ => {
+                        ip_reg = r;
+                        Ok(())
+                    }
+                })
+            } else {
+                OpCode::parse(line, registers, extensions).map(|op| program.push(op))
+            };
+
+            if let Err(e) = res {
+                errors.push(e.at_line(i + 1));
             }
         }
 
-        Machine::new(ip_reg, program, Registers::default())
+        if errors.is_empty() {
+            Ok(Machine::new(ip_reg, program, Registers::default()))
+        } else {
+            Err(ParseErrors(errors))
+        }
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-pub struct Registers<const N: usize> {
-    inner: [i32; N],
+impl<const N: usize, W: Word> fmt::Display for Machine<N, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "#ip {}", self.ip_reg)?;
+        for op in &self.program {
+            writeln!(f, "{}", op)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Registers<const N: usize, W: Word = i32> {
+    inner: [W; N],
 }
 
-impl<const N: usize> Registers<N> {
-    pub fn new(inner: [i32; N]) -> Registers<N> {
+impl<const N: usize, W: Word> Registers<N, W> {
+    pub fn new(inner: [W; N]) -> Registers<N, W> {
         Registers { inner }
     }
 
-    pub fn get(&self, i: &i32) -> i32 {
-        *self.inner.get(*i as usize).unwrap_or(&0)
+    pub fn get(&self, i: &i32) -> W {
+        self.inner.get(*i as usize).cloned().unwrap_or_else(W::zero)
     }
 
-    pub fn set(&mut self, i: &i32, val: i32) {
+    pub fn set(&mut self, i: &i32, val: W) {
         let i = *i as usize;
         if i < self.inner.len() {
             self.inner[i] = val;
@@ -88,16 +387,23 @@ impl<const N: usize> Registers<N> {
     }
 
     fn default() -> Self {
-        Registers { inner: [0; N] }
+        Registers {
+            inner: std::array::from_fn(|_| W::zero()),
+        }
     }
 
-    pub fn all(&self) -> [i32; N] {
-        self.inner
+    pub fn all(&self) -> [W; N] {
+        self.inner.clone()
     }
 }
 
+fn register_range(registers: usize) -> String {
+    format!("a register between r0 and r{}", registers - 1)
+}
+
 lazy_static! {
-    static ref OP_RE: Regex = Regex::new(r"([[:alpha:]]+) (\d+) (\d+) (\d+)").unwrap();
+    static ref IP_RE: Regex = Regex::new(r"^#ip (\d+)$").unwrap();
+    static ref OP_RE: Regex = Regex::new(r"^([[:alpha:]]+) (\d+) (\d+) (\d+)$").unwrap();
 }
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
@@ -118,39 +424,51 @@ pub enum OpCode {
     EqIr(i32, i32, i32),
     EqRi(i32, i32, i32),
     EqRr(i32, i32, i32),
+    Ext(Ext, i32, i32, i32),
     Nop,
 }
 
 impl OpCode {
-    pub fn apply<const T: usize>(&self, input: &Registers<T>) -> Registers<T> {
+    pub fn apply<const T: usize, W: Word>(&self, input: &Registers<T, W>) -> Registers<T, W> {
+        self.apply_with(input, Overflow::Wrap).unwrap()
+    }
+
+    // None when an addition or multiplication traps
+    pub fn apply_with<const T: usize, W: Word>(
+        &self,
+        input: &Registers<T, W>,
+        overflow: Overflow,
+    ) -> Option<Registers<T, W>> {
         let mut res = input.clone();
+        let imm = |v: &i32| W::from_i32(*v);
+        let flag = |v: bool| if v { W::one() } else { W::zero() };
         match self {
-            OpCode::AddR(a, b, c) => res.set(c, res.get(a).overflowing_add(res.get(b)).0),
-            OpCode::AddI(a, b, c) => res.set(c, res.get(a).overflowing_add(*b).0),
+            OpCode::AddR(a, b, c) => res.set(c, res.get(a).add_with(res.get(b), overflow)?),
+            OpCode::AddI(a, b, c) => res.set(c, res.get(a).add_with(imm(b), overflow)?),
 
-            OpCode::MulR(a, b, c) => res.set(c, res.get(a).overflowing_mul(res.get(b)).0),
-            OpCode::MulI(a, b, c) => res.set(c, res.get(a).overflowing_mul(*b).0),
+            OpCode::MulR(a, b, c) => res.set(c, res.get(a).mul_with(res.get(b), overflow)?),
+            OpCode::MulI(a, b, c) => res.set(c, res.get(a).mul_with(imm(b), overflow)?),
 
             OpCode::BanR(a, b, c) => res.set(c, res.get(a) & res.get(b)),
-            OpCode::BanI(a, b, c) => res.set(c, res.get(a) & b),
+            OpCode::BanI(a, b, c) => res.set(c, res.get(a) & imm(b)),
 
             OpCode::BorR(a, b, c) => res.set(c, res.get(a) | res.get(b)),
-            OpCode::BorI(a, b, c) => res.set(c, res.get(a) | b),
+            OpCode::BorI(a, b, c) => res.set(c, res.get(a) | imm(b)),
 
             OpCode::SetR(a, _, c) => res.set(c, res.get(a)),
-            OpCode::SetI(a, _, c) => res.set(c, *a),
+            OpCode::SetI(a, _, c) => res.set(c, imm(a)),
 
-            OpCode::GtIr(a, b, c) => res.set(c, if *a > res.get(b) { 1 } else { 0 }),
-            OpCode::GtRi(a, b, c) => res.set(c, if res.get(a) > *b { 1 } else { 0 }),
-            OpCode::GtRr(a, b, c) => res.set(c, if res.get(a) > res.get(b) { 1 } else { 0 }),
+            OpCode::GtIr(a, b, c) => res.set(c, flag(imm(a) > res.get(b))),
+            OpCode::GtRi(a, b, c) => res.set(c, flag(res.get(a) > imm(b))),
+            OpCode::GtRr(a, b, c) => res.set(c, flag(res.get(a) > res.get(b))),
 
-            OpCode::EqIr(a, b, c) => res.set(c, if *a == res.get(b) { 1 } else { 0 }),
-            OpCode::EqRi(a, b, c) => res.set(c, if res.get(a) == *b { 1 } else { 0 }),
-            OpCode::EqRr(a, b, c) => res.set(c, if res.get(a) == res.get(b) { 1 } else { 0 }),
+            OpCode::EqIr(a, b, c) => res.set(c, flag(imm(a) == res.get(b))),
+            OpCode::EqRi(a, b, c) => res.set(c, flag(res.get(a) == imm(b))),
+            OpCode::EqRr(a, b, c) => res.set(c, flag(res.get(a) == res.get(b))),
             _ => {}
         }
 
-        res
+        Some(res)
     }
 
     pub fn get_default(&self) -> OpCode {
@@ -171,40 +489,279 @@ impl OpCode {
             OpCode::EqIr(_, _, _) => OpCode::EqIr(0, 0, 0),
             OpCode::EqRi(_, _, _) => OpCode::EqRi(0, 0, 0),
             OpCode::EqRr(_, _, _) => OpCode::EqRr(0, 0, 0),
+            OpCode::Ext(ext, _, _, _) => OpCode::Ext(*ext, 0, 0, 0),
             _ => *self,
         }
     }
 
-    fn parse(input: &str) -> Option<OpCode> {
-        if let Some(cap) = OP_RE.captures(input) {
-            let o: String = parse_capture(&cap, 1, "o").unwrap();
-            let a = parse_capture(&cap, 2, "a").unwrap();
-            let b = parse_capture(&cap, 3, "b").unwrap();
-            let c = parse_capture(&cap, 4, "c").unwrap();
-
-            let op = match o.as_str() {
-                "addr" => OpCode::AddR(a, b, c),
-                "addi" => OpCode::AddI(a, b, c),
-                "mulr" => OpCode::MulR(a, b, c),
-                "muli" => OpCode::MulI(a, b, c),
-                "banr" => OpCode::BanR(a, b, c),
-                "bani" => OpCode::BanI(a, b, c),
-                "borr" => OpCode::BorR(a, b, c),
-                "bori" => OpCode::BorI(a, b, c),
-                "setr" => OpCode::SetR(a, b, c),
-                "seti" => OpCode::SetI(a, b, c),
-                "gtir" => OpCode::GtIr(a, b, c),
-                "gtri" => OpCode::GtRi(a, b, c),
-                "gtrr" => OpCode::GtRr(a, b, c),
-                "eqir" => OpCode::EqIr(a, b, c),
-                "eqri" => OpCode::EqRi(a, b, c),
-                "eqrr" => OpCode::EqRr(a, b, c),
-                _ => OpCode::Nop,
-            };
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            OpCode::AddR(_, _, _) => "addr",
+            OpCode::AddI(_, _, _) => "addi",
+            OpCode::MulR(_, _, _) => "mulr",
+            OpCode::MulI(_, _, _) => "muli",
+            OpCode::BanR(_, _, _) => "banr",
+            OpCode::BanI(_, _, _) => "bani",
+            OpCode::BorR(_, _, _) => "borr",
+            OpCode::BorI(_, _, _) => "bori",
+            OpCode::SetR(_, _, _) => "setr",
+            OpCode::SetI(_, _, _) => "seti",
+            OpCode::GtIr(_, _, _) => "gtir",
+            OpCode::GtRi(_, _, _) => "gtri",
+            OpCode::GtRr(_, _, _) => "gtrr",
+            OpCode::EqIr(_, _, _) => "eqir",
+            OpCode::EqRi(_, _, _) => "eqri",
+            OpCode::EqRr(_, _, _) => "eqrr",
+            OpCode::Ext(ext, _, _, _) => ext.mnemonic,
+            OpCode::Nop => "nop",
+        }
+    }
 
-            Some(op)
-        } else {
-            None
+    pub fn operands(&self) -> Option<(i32, i32, i32)> {
+        match *self {
+            OpCode::AddR(a, b, c)
+            | OpCode::AddI(a, b, c)
+            | OpCode::MulR(a, b, c)
+            | OpCode::MulI(a, b, c)
+            | OpCode::BanR(a, b, c)
+            | OpCode::BanI(a, b, c)
+            | OpCode::BorR(a, b, c)
+            | OpCode::BorI(a, b, c)
+            | OpCode::SetR(a, b, c)
+            | OpCode::SetI(a, b, c)
+            | OpCode::GtIr(a, b, c)
+            | OpCode::GtRi(a, b, c)
+            | OpCode::GtRr(a, b, c)
+            | OpCode::EqIr(a, b, c)
+            | OpCode::EqRi(a, b, c)
+            | OpCode::EqRr(a, b, c)
+            | OpCode::Ext(_, a, b, c) => Some((a, b, c)),
+            OpCode::Nop => None,
+        }
+    }
+
+    // whether the a and b operands name registers rather than immediate values
+    pub fn register_operands(&self) -> (bool, bool) {
+        match self {
+            OpCode::AddR(_, _, _)
+            | OpCode::MulR(_, _, _)
+            | OpCode::BanR(_, _, _)
+            | OpCode::BorR(_, _, _)
+            | OpCode::GtRr(_, _, _)
+            | OpCode::EqRr(_, _, _) => (true, true),
+            OpCode::AddI(_, _, _)
+            | OpCode::MulI(_, _, _)
+            | OpCode::BanI(_, _, _)
+            | OpCode::BorI(_, _, _)
+            | OpCode::SetR(_, _, _)
+            | OpCode::GtRi(_, _, _)
+            | OpCode::EqRi(_, _, _) => (true, false),
+            OpCode::GtIr(_, _, _) | OpCode::EqIr(_, _, _) => (false, true),
+            OpCode::SetI(_, _, _) | OpCode::Nop => (false, false),
+            OpCode::Ext(ext, _, _, _) => ext.registers,
+        }
+    }
+
+    pub fn with_operands(&self, a: i32, b: i32, c: i32) -> OpCode {
+        match self {
+            OpCode::AddR(_, _, _) => OpCode::AddR(a, b, c),
+            OpCode::AddI(_, _, _) => OpCode::AddI(a, b, c),
+            OpCode::MulR(_, _, _) => OpCode::MulR(a, b, c),
+            OpCode::MulI(_, _, _) => OpCode::MulI(a, b, c),
+            OpCode::BanR(_, _, _) => OpCode::BanR(a, b, c),
+            OpCode::BanI(_, _, _) => OpCode::BanI(a, b, c),
+            OpCode::BorR(_, _, _) => OpCode::BorR(a, b, c),
+            OpCode::BorI(_, _, _) => OpCode::BorI(a, b, c),
+            OpCode::SetR(_, _, _) => OpCode::SetR(a, b, c),
+            OpCode::SetI(_, _, _) => OpCode::SetI(a, b, c),
+            OpCode::GtIr(_, _, _) => OpCode::GtIr(a, b, c),
+            OpCode::GtRi(_, _, _) => OpCode::GtRi(a, b, c),
+            OpCode::GtRr(_, _, _) => OpCode::GtRr(a, b, c),
+            OpCode::EqIr(_, _, _) => OpCode::EqIr(a, b, c),
+            OpCode::EqRi(_, _, _) => OpCode::EqRi(a, b, c),
+            OpCode::EqRr(_, _, _) => OpCode::EqRr(a, b, c),
+            OpCode::Ext(ext, _, _, _) => OpCode::Ext(*ext, a, b, c),
+            OpCode::Nop => OpCode::Nop,
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str, a: i32, b: i32, c: i32) -> Option<OpCode> {
+        let op = match mnemonic {
+            "addr" => OpCode::AddR(a, b, c),
+            "addi" => OpCode::AddI(a, b, c),
+            "mulr" => OpCode::MulR(a, b, c),
+            "muli" => OpCode::MulI(a, b, c),
+            "banr" => OpCode::BanR(a, b, c),
+            "bani" => OpCode::BanI(a, b, c),
+            "borr" => OpCode::BorR(a, b, c),
+            "bori" => OpCode::BorI(a, b, c),
+            "setr" => OpCode::SetR(a, b, c),
+            "seti" => OpCode::SetI(a, b, c),
+            "gtir" => OpCode::GtIr(a, b, c),
+            "gtri" => OpCode::GtRi(a, b, c),
+            "gtrr" => OpCode::GtRr(a, b, c),
+            "eqir" => OpCode::EqIr(a, b, c),
+            "eqri" => OpCode::EqRi(a, b, c),
+            "eqrr" => OpCode::EqRr(a, b, c),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    // the first register operand outside of 0..registers
+    pub fn invalid_register(&self, registers: usize) -> Option<i32> {
+        let (a, b, c) = self.operands()?;
+        let (reg_a, reg_b) = self.register_operands();
+        [(a, reg_a), (b, reg_b), (c, true)]
+            .iter()
+            .find(|&&(r, register)| register && (r < 0 || r as usize >= registers))
+            .map(|&(r, _)| r)
+    }
+
+    // with `registers`, unknown mnemonics and out of range registers are errors
+    fn parse(
+        input: &str,
+        registers: Option<usize>,
+        extensions: &[Ext],
+    ) -> Result<OpCode, ParseError> {
+        let cap = OP_RE
+            .captures(input)
+            .ok_or_else(|| ParseError::no_match(input, &OP_RE))?;
+        let o: String = parse_capture(&cap, 1, "o")?;
+        let a = parse_capture(&cap, 2, "a")?;
+        let b = parse_capture(&cap, 3, "b")?;
+        let c = parse_capture(&cap, 4, "c")?;
+
+        let op = OpCode::from_mnemonic(&o, a, b, c).or_else(|| {
+            let ext = extensions.iter().find(|e| e.mnemonic == o)?;
+            Some(OpCode::Ext(*ext, a, b, c))
+        });
+        let n = match registers {
+            Some(n) => n,
+            None => return Ok(op.unwrap_or(OpCode::Nop)),
+        };
+        let op = op.ok_or_else(|| ParseError::new(1, &o, "an instruction"))?;
+
+        let (reg_a, reg_b) = op.register_operands();
+        for &(idx, register) in [(2, reg_a), (3, reg_b), (4, true)].iter() {
+            let m = cap.get(idx).unwrap();
+            if register && m.as_str().parse::<usize>().map_or(true, |r| r >= n) {
+                return Err(ParseError::new(
+                    m.start() + 1,
+                    m.as_str(),
+                    &register_range(n),
+                ));
+            }
+        }
+        Ok(op)
+    }
+}
+
+impl fmt::Display for OpCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.operands() {
+            Some((a, b, c)) => write!(f, "{} {} {} {}", self.mnemonic(), a, b, c),
+            None => write!(f, "{}", self.mnemonic()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_strict() {
+        let input = [
+            "#ip 6",
+            "seti 1 0 1",
+            "adr 0 1 0",
+            "addr 0 7 0",
+            "addi 0 7 6",
+        ];
+        assert!(Machine::<6>::parse(&input).is_ok());
+
+        let errors = Machine::<6>::parse_strict(&input).err().unwrap().0;
+        let found: Vec<(usize, usize, &str)> = errors
+            .iter()
+            .map(|e| (e.line, e.column, e.text.as_str()))
+            .collect();
+        assert_eq!(
+            vec![(1, 5, "6"), (3, 1, "adr"), (4, 8, "7"), (5, 10, "6")],
+            found
+        );
+        assert_eq!(
+            "line 4, column 8: expected a register between r0 and r5, found \"7\"",
+            errors[2].to_string()
+        );
+    }
+
+    #[test]
+    fn test_faults() {
+        for engine in [Engine::Interpreter, Engine::Compiled] {
+            let mut machine = Machine::<4>::parse(&["#ip 3", "seti 1 0 1", "mul 1 1 1"]).unwrap();
+            machine.set_engine(engine);
+            machine.run();
+            assert_eq!(
+                MachineState::Faulted(Fault::UnknownInstruction(1)),
+                machine.state
+            );
+            assert_eq!(1, machine.registers.get(&1));
+
+            let mut machine = Machine::<4>::parse(&["#ip 3", "seti 1 0 1", "addr 1 4 2"]).unwrap();
+            machine.set_engine(engine);
+            machine.run();
+            assert_eq!(
+                MachineState::Faulted(Fault::InvalidRegister(1, 4)),
+                machine.state
+            );
+
+            let mut machine = Machine::<4>::parse(&["#ip 4", "seti 1 0 1"]).unwrap();
+            machine.set_engine(engine);
+            machine.run();
+            assert_eq!(
+                MachineState::Faulted(Fault::InvalidRegister(0, 4)),
+                machine.state
+            );
+        }
+    }
+
+    const HASH: [&str; 4] = [
+        "#ip 5",
+        "seti 16777215 0 1",
+        "muli 1 65899 1",
+        "bani 1 16777215 1",
+    ];
+
+    fn hash<W: Word>(engine: Engine, overflow: Overflow) -> Machine<6, W> {
+        let mut machine = Machine::<6, W>::parse(&HASH).unwrap();
+        machine.set_overflow(overflow);
+        machine.set_engine(engine);
+        machine.run();
+        machine
+    }
+
+    #[test]
+    fn test_overflow() {
+        let exact = (16_777_215 * 65_899) & 16_777_215;
+        for engine in [Engine::Interpreter, Engine::Compiled] {
+            let wrapped = hash::<i32>(engine, Overflow::Wrap);
+            assert_eq!(MachineState::Halted, wrapped.state);
+            assert_eq!(exact as i32, wrapped.registers.get(&1));
+
+            let saturated = hash::<i32>(engine, Overflow::Saturate);
+            assert_eq!(16_777_215, saturated.registers.get(&1));
+
+            let trapped = hash::<i32>(engine, Overflow::Trap);
+            assert_eq!(MachineState::Faulted(Fault::Overflow(1)), trapped.state);
+            assert_eq!(16_777_215, trapped.registers.get(&1));
+
+            let wide = hash::<i64>(engine, Overflow::Trap);
+            assert_eq!(MachineState::Halted, wide.state);
+            assert_eq!(exact, wide.registers.get(&1));
+
+            let unsigned = hash::<u64>(engine, Overflow::Trap);
+            assert_eq!(exact as u64, unsigned.registers.get(&1));
         }
     }
 }
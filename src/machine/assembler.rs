@@ -0,0 +1,440 @@
+use std::collections::{BTreeMap, HashMap};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::machine::*;
+
+// the assembler accepts everything `Machine::parse` does, the instructions of a registry
+// when given one, plus:
+//
+//   ; comment            everything after a `;` is ignored
+//   #ip <register>       the register bound to the instruction pointer, can be an alias
+//   .reg <name> <reg>    names a register, written `r3` or `3`
+//   .const <name> <n>    names a value
+//   <label>:             names the address of the next instruction
+//   jmp <target>         continues at target, short for `seti <target>-1 0 <ip register>`
+//
+// value operands are numbers, constants or labels, optionally followed by `+k` or `-k`;
+// register operands are numbers, `r<n>` or aliases
+
+lazy_static! {
+    static ref NAME_RE: Regex = Regex::new(r"^[[:alpha:]_][[:alnum:]_]*$").unwrap();
+    static ref REG_RE: Regex = Regex::new(r"^r?(\d+)$").unwrap();
+    static ref VALUE_RE: Regex =
+        Regex::new(r"^(?:(-?\d+)|([[:alpha:]_][[:alnum:]_]*)(?:([+-])(\d+))?)$").unwrap();
+}
+
+pub struct Assembly {
+    pub ip_reg: u8,
+    pub program: Vec<OpCode>,
+    pub labels: BTreeMap<String, i32>,
+}
+
+#[derive(Clone, Copy)]
+enum Symbol {
+    Register(i32),
+    Value(i32),
+}
+
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    column: usize,
+    text: &'a str,
+}
+
+impl<'a> Token<'a> {
+    fn error(&self, expected: &str) -> ParseError {
+        ParseError::new(self.column, self.text, expected)
+    }
+
+    fn end(&self) -> usize {
+        self.column + self.text.len()
+    }
+}
+
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let code = match line.find(';') {
+        Some(i) => &line[..i],
+        None => line,
+    };
+    code.split_whitespace()
+        .map(|text| Token {
+            column: text.as_ptr() as usize - code.as_ptr() as usize + 1,
+            text,
+        })
+        .collect()
+}
+
+// the operands following tokens[0], which must be exactly n
+fn operands<'a, 'b>(tokens: &'b [Token<'a>], n: usize) -> Result<&'b [Token<'a>], ParseError> {
+    match tokens.len() - 1 {
+        len if len < n => {
+            let last = tokens[tokens.len() - 1];
+            Err(ParseError::new(last.end(), "", "an operand"))
+        }
+        len if len > n => Err(tokens[n + 1].error("end of line")),
+        _ => Ok(&tokens[1..]),
+    }
+}
+
+struct Assembler<'a> {
+    registers: usize,
+    extensions: &'a [Ext],
+    symbols: HashMap<String, Symbol>,
+    labels: BTreeMap<String, i32>,
+}
+
+impl Assembler<'_> {
+    fn define(&mut self, name: Token, symbol: Symbol) -> Result<(), ParseError> {
+        if !NAME_RE.is_match(name.text) || REG_RE.is_match(name.text) {
+            return Err(name.error("a name"));
+        }
+        if self.symbols.contains_key(name.text) {
+            return Err(name.error("a name not defined before"));
+        }
+        self.symbols.insert(String::from(name.text), symbol);
+        Ok(())
+    }
+
+    fn register_literal(&self, token: Token) -> Result<i32, ParseError> {
+        let expected = register_range(self.registers);
+        let cap = REG_RE
+            .captures(token.text)
+            .ok_or_else(|| token.error(&expected))?;
+        match cap[1].parse::<usize>() {
+            Ok(r) if r < self.registers => Ok(r as i32),
+            _ => Err(token.error(&expected)),
+        }
+    }
+
+    fn register(&self, token: Token) -> Result<i32, ParseError> {
+        match self.symbols.get(token.text) {
+            Some(Symbol::Register(r)) => Ok(*r),
+            Some(Symbol::Value(_)) => Err(token.error("a register")),
+            None => self.register_literal(token),
+        }
+    }
+
+    fn value(&self, token: Token) -> Result<i32, ParseError> {
+        let cap = VALUE_RE
+            .captures(token.text)
+            .ok_or_else(|| token.error("a value"))?;
+        if let Some(n) = cap.get(1) {
+            return n
+                .as_str()
+                .parse()
+                .map_err(|_| token.error("a 32 bit value"));
+        }
+
+        let base = match self.symbols.get(&cap[2]) {
+            Some(Symbol::Value(v)) => *v,
+            Some(Symbol::Register(_)) => return Err(token.error("a value, not a register")),
+            None => return Err(token.error("a defined name")),
+        };
+        let offset = match cap.get(4) {
+            Some(k) => k.as_str().parse().ok(),
+            None => Some(0),
+        };
+        let res = match cap.get(3).map(|s| s.as_str()) {
+            Some("-") => offset.and_then(|k| base.checked_sub(k)),
+            _ => offset.and_then(|k| base.checked_add(k)),
+        };
+        res.ok_or_else(|| token.error("a 32 bit value"))
+    }
+
+    fn directive(&mut self, tokens: &[Token]) -> Result<(), ParseError> {
+        match tokens[0].text {
+            ".reg" => {
+                let args = operands(tokens, 2)?;
+                let r = self.register_literal(args[1])?;
+                self.define(args[0], Symbol::Register(r))
+            }
+            ".const" => {
+                let args = operands(tokens, 2)?;
+                let v = args[1]
+                    .text
+                    .parse()
+                    .map_err(|_| args[1].error("a number"))?;
+                self.define(args[0], Symbol::Value(v))
+            }
+            _ => Err(tokens[0].error("a directive (.reg or .const)")),
+        }
+    }
+
+    fn instruction(&self, tokens: &[Token], ip_reg: Option<i32>) -> Result<OpCode, ParseError> {
+        let mnemonic = tokens[0];
+        if mnemonic.text == "jmp" {
+            let target = self.value(operands(tokens, 1)?[0])?;
+            let ip_reg = ip_reg.ok_or_else(|| mnemonic.error("an #ip declaration"))?;
+            return Ok(OpCode::SetI(target.wrapping_sub(1), 0, ip_reg));
+        }
+
+        let op = OpCode::from_mnemonic(mnemonic.text, 0, 0, 0)
+            .or_else(|| {
+                let ext = self
+                    .extensions
+                    .iter()
+                    .find(|e| e.mnemonic == mnemonic.text)?;
+                Some(OpCode::Ext(*ext, 0, 0, 0))
+            })
+            .ok_or_else(|| mnemonic.error("an instruction"))?;
+        let args = operands(tokens, 3)?;
+        let (reg_a, reg_b) = op.register_operands();
+        let operand = |token, register| match register {
+            true => self.register(token),
+            false => self.value(token),
+        };
+        Ok(op.with_operands(
+            operand(args[0], reg_a)?,
+            operand(args[1], reg_b)?,
+            self.register(args[2])?,
+        ))
+    }
+}
+
+pub fn assemble(
+    input: &[&str],
+    registers: usize,
+    extensions: &[Ext],
+) -> Result<Assembly, ParseErrors> {
+    let mut asm = Assembler {
+        registers,
+        extensions,
+        symbols: HashMap::new(),
+        labels: BTreeMap::new(),
+    };
+    let mut errors = Vec::new();
+    let mut ip_decl = None;
+    let mut instructions = Vec::new();
+
+    // the first pass collects the definitions, so that they can be used before them
+    for (i, line) in input.iter().enumerate() {
+        let mut tokens = tokenize(line);
+        if let Some(label) = tokens.first().and_then(|t| t.text.strip_suffix(':')) {
+            let name = Token {
+                column: tokens[0].column,
+                text: label,
+            };
+            let address = instructions.len() as i32;
+            match asm.define(name, Symbol::Value(address)) {
+                Ok(()) => {
+                    asm.labels.insert(String::from(label), address);
+                }
+                Err(e) => errors.push(e.at_line(i + 1)),
+            }
+            tokens.remove(0);
+        }
+
+        let res = match tokens.first() {
+            None => Ok(()),
+            Some(t) if t.text == "#ip" => match ip_decl {
+                Some(_) => Err(t.error("a single #ip declaration")),
+                None => operands(&tokens, 1).map(|args| ip_decl = Some((i, args[0]))),
+            },
+            Some(t) if t.text.starts_with('.') => asm.directive(&tokens),
+            Some(_) => {
+                instructions.push((i, tokens));
+                Ok(())
+            }
+        };
+        if let Err(e) = res {
+            errors.push(e.at_line(i + 1));
+        }
+    }
+
+    let ip_reg = ip_decl.map(|(i, token)| {
+        asm.register(token).unwrap_or_else(|e| {
+            errors.push(e.at_line(i + 1));
+            0
+        })
+    });
+
+    let mut program = Vec::with_capacity(instructions.len());
+    for (i, tokens) in instructions {
+        match asm.instruction(&tokens, ip_reg) {
+            Ok(op) => program.push(op),
+            Err(e) => errors.push(e.at_line(i + 1)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(Assembly {
+            ip_reg: ip_reg.unwrap_or(0) as u8,
+            program,
+            labels: asm.labels,
+        })
+    } else {
+        errors.sort_by_key(|e| (e.line, e.column));
+        Err(ParseErrors(errors))
+    }
+}
+
+impl<const N: usize, W: Word> Machine<N, W> {
+    pub fn assemble(input: &[&str]) -> Result<Machine<N, W>, ParseErrors> {
+        Machine::assemble_extended(input, Registry::default())
+    }
+
+    // like `assemble`, also accepting the instructions of the registry
+    pub fn assemble_extended(
+        input: &[&str],
+        registry: Registry<W>,
+    ) -> Result<Machine<N, W>, ParseErrors> {
+        let assembly = assemble(input, N, &registry.extensions())?;
+        let mut machine = Machine::new(assembly.ip_reg, assembly.program, Registers::default());
+        machine.registry = registry;
+        Ok(machine)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_assemble() {
+        let input = [
+            "; sums the numbers from 1 to LIMIT into r0",
+            "#ip ip",
+            ".reg ip r5",
+            ".reg sum r0",
+            ".reg i 1",
+            ".reg flag r2",
+            ".const LIMIT 10",
+            "",
+            "        seti 1 0 i",
+            "loop:   addr sum i sum",
+            "        addi i 1 i",
+            "        gtri i LIMIT flag",
+            "        addr flag ip ip     ; leaves the loop once i > LIMIT",
+            "        jmp loop",
+        ];
+        let assembly = assemble(&input, 6, &[]).unwrap();
+        assert_eq!(5, assembly.ip_reg);
+        assert_eq!(
+            vec![
+                OpCode::SetI(1, 0, 1),
+                OpCode::AddR(0, 1, 0),
+                OpCode::AddI(1, 1, 1),
+                OpCode::GtRi(1, 10, 2),
+                OpCode::AddR(2, 5, 5),
+                OpCode::SetI(0, 0, 5),
+            ],
+            assembly.program
+        );
+        assert_eq!(Some(&1), assembly.labels.get("loop"));
+
+        let mut machine = Machine::<6>::assemble(&input).unwrap();
+        machine.run();
+        assert_eq!(55, machine.registers.get(&0));
+    }
+
+    #[test]
+    fn test_plain() {
+        let input = [
+            "#ip 10",
+            "seti 5 0 1",
+            "addr 1 2 3",
+            "eqri 3 7 10",
+            "gtir 4 3 2",
+        ];
+        let assembled = Machine::<11>::assemble(&input).unwrap();
+        let parsed = Machine::<11>::parse(&input).unwrap();
+        assert_eq!(10, assembled.ip_reg);
+        assert_eq!(parsed.ip_reg, assembled.ip_reg);
+        assert_eq!(parsed.program(), assembled.program());
+    }
+
+    #[test]
+    fn test_offsets() {
+        let input = [
+            "#ip r3",
+            ".const BASE -2",
+            "start: seti end+1 BASE 0",
+            "end:   addi 0 start-1 1",
+        ];
+        let assembly = assemble(&input, 4, &[]).unwrap();
+        assert_eq!(
+            vec![OpCode::SetI(2, -2, 0), OpCode::AddI(0, -1, 1)],
+            assembly.program
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let input = [
+            "#ip 6",
+            "start: seti 0 0 0",
+            "start: addi 0 1 0",
+            "mulx 0 1 0",
+            "addi 0 missing 0",
+            "seti 1 2",
+            "jmp start extra ; comment",
+            ".reg r1 2",
+            "addr 0 r1 start",
+        ];
+        let errors = assemble(&input, 6, &[]).err().unwrap().0;
+        let found: Vec<(usize, usize, &str)> = errors
+            .iter()
+            .map(|e| (e.line, e.column, e.text.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, 5, "6"),
+                (3, 1, "start"),
+                (4, 1, "mulx"),
+                (5, 8, "missing"),
+                (6, 9, ""),
+                (7, 11, "extra"),
+                (8, 6, "r1"),
+                (9, 11, "start"),
+            ],
+            found
+        );
+        assert_eq!(
+            "line 1, column 5: expected a register between r0 and r5, found \"6\"",
+            errors[0].to_string()
+        );
+        assert_eq!(
+            "line 5, column 8: expected a defined name, found \"missing\"",
+            errors[3].to_string()
+        );
+    }
+
+    #[test]
+    fn test_jump_without_ip() {
+        let errors = assemble(&["loop: jmp loop"], 6, &[]).err().unwrap().0;
+        assert_eq!(
+            "line 1, column 7: expected an #ip declaration, found \"jmp\"",
+            errors[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_extended() {
+        let input = [
+            "#ip ip",
+            ".reg ip 5",
+            ".reg n r1",
+            "in 0 0 n",
+            "loop: modi n 10 r2",
+            "divi n 10 n",
+            "out r2 0 0",
+            "eqri n 0 r2",
+            "addr r2 ip ip",
+            "jmp loop",
+            "halt 0 0 0",
+        ];
+        let mut machine = Machine::<6>::assemble_extended(&input, Registry::extended()).unwrap();
+        machine.io.input.push_back(1984);
+        machine.run();
+        assert_eq!(vec![4, 8, 9, 1], machine.io.output);
+        assert_eq!(MachineState::Halted, machine.state);
+
+        let errors = Machine::<6>::assemble(&input).err().unwrap().0;
+        assert_eq!(
+            "line 4, column 1: expected an instruction, found \"in\"",
+            errors[0].to_string()
+        );
+    }
+}
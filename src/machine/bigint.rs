@@ -0,0 +1,511 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, BitAnd, BitOr, Div, Mul, Neg, Rem, Sub};
+use std::str::FromStr;
+
+use crate::machine::Word;
+
+// an integer of any size: a sign and a magnitude in base 2^32, least significant digit
+// first and without leading zeros, so that zero has no digits and is never negative
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> BigInt {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let negative = negative && !digits.is_empty();
+        BigInt { negative, digits }
+    }
+
+    fn from_i128(v: i128) -> BigInt {
+        let mut magnitude = v.unsigned_abs();
+        let mut digits = Vec::new();
+        while magnitude > 0 {
+            digits.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+        BigInt::new(v < 0, digits)
+    }
+
+    // None when it doesn't fit
+    fn to_i128(&self) -> Option<i128> {
+        if self.digits.len() > 4 {
+            return None;
+        }
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0u128, |acc, &d| (acc << 32) | d as u128);
+        match self.negative {
+            true if magnitude <= 1 << 127 => Some((magnitude as i128).wrapping_neg()),
+            false if magnitude <= i128::MAX as u128 => Some(magnitude as i128),
+            _ => None,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    // the last digits of the two's complement, with room for the sign bit
+    fn twos(&self, len: usize) -> Vec<u32> {
+        let mut res = self.digits.clone();
+        res.resize(len, 0);
+        if self.negative {
+            let mut carry = true;
+            for d in res.iter_mut() {
+                let (v, c) = (!*d).overflowing_add(carry as u32);
+                *d = v;
+                carry = c;
+            }
+        }
+        res
+    }
+
+    fn from_twos(mut digits: Vec<u32>) -> BigInt {
+        let negative = digits.last().is_some_and(|top| top >> 31 == 1);
+        if negative {
+            let mut carry = true;
+            for d in digits.iter_mut() {
+                let (v, c) = (!*d).overflowing_add(carry as u32);
+                *d = v;
+                carry = c;
+            }
+        }
+        BigInt::new(negative, digits)
+    }
+
+    fn bitwise(&self, other: &BigInt, f: impl Fn(u32, u32) -> u32) -> BigInt {
+        let len = self.digits.len().max(other.digits.len()) + 1;
+        let (a, b) = (self.twos(len), other.twos(len));
+        BigInt::from_twos(a.iter().zip(&b).map(|(&x, &y)| f(x, y)).collect())
+    }
+}
+
+fn compare(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        res.push(sum as u32);
+        carry = sum >> 32;
+    }
+    res.push(carry as u32);
+    res
+}
+
+// a - b, for a >= b
+fn sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = Vec::with_capacity(a.len());
+    let mut borrow = false;
+    for (i, &x) in a.iter().enumerate() {
+        let (v, b1) = x.overflowing_sub(*b.get(i).unwrap_or(&0));
+        let (v, b2) = v.overflowing_sub(borrow as u32);
+        res.push(v);
+        borrow = b1 || b2;
+    }
+    res
+}
+
+fn mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let v = res[i + j] as u64 + x as u64 * y as u64 + carry;
+            res[i + j] = v as u32;
+            carry = v >> 32;
+        }
+        res[i + b.len()] = carry as u32;
+    }
+    res
+}
+
+// the quotient and remainder of the magnitudes, one bit at a time; b isn't zero
+fn div_rem(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut rem: Vec<u32> = Vec::new();
+    for bit in (0..a.len() * 32).rev() {
+        // rem = rem * 2 + the next bit of a
+        let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+        for d in rem.iter_mut() {
+            let top = *d >> 31;
+            *d = (*d << 1) | carry;
+            carry = top;
+        }
+        if carry == 1 {
+            rem.push(1);
+        }
+        if compare(&rem, b) != Ordering::Less {
+            rem = BigInt::new(false, sub(&rem, b)).digits;
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    (quotient, rem)
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare(&self.digits, &other.digits),
+            (true, true) => compare(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.digits)
+    }
+}
+
+impl Add for BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add(&self.digits, &other.digits));
+        }
+        match compare(&self.digits, &other.digits) {
+            Ordering::Less => BigInt::new(other.negative, sub(&other.digits, &self.digits)),
+            _ => BigInt::new(self.negative, sub(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl Sub for BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: BigInt) -> BigInt {
+        self + -other
+    }
+}
+
+impl Mul for BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != other.negative,
+            mul(&self.digits, &other.digits),
+        )
+    }
+}
+
+// like the primitive integers, division truncates and the remainder takes the sign of the
+// dividend
+impl Div for BigInt {
+    type Output = BigInt;
+
+    fn div(self, other: BigInt) -> BigInt {
+        assert!(!other.is_zero(), "attempt to divide by zero");
+        let (quotient, _) = div_rem(&self.digits, &other.digits);
+        BigInt::new(self.negative != other.negative, quotient)
+    }
+}
+
+impl Rem for BigInt {
+    type Output = BigInt;
+
+    fn rem(self, other: BigInt) -> BigInt {
+        assert!(
+            !other.is_zero(),
+            "attempt to calculate the remainder with a divisor of zero"
+        );
+        let (_, rem) = div_rem(&self.digits, &other.digits);
+        BigInt::new(self.negative, rem)
+    }
+}
+
+// on the two's complement, as for the primitive integers
+impl BitAnd for BigInt {
+    type Output = BigInt;
+
+    fn bitand(self, other: BigInt) -> BigInt {
+        self.bitwise(&other, |x, y| x & y)
+    }
+}
+
+impl BitOr for BigInt {
+    type Output = BigInt;
+
+    fn bitor(self, other: BigInt) -> BigInt {
+        self.bitwise(&other, |x, y| x | y)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // groups of 9 decimal digits, least significant first
+        let mut groups = Vec::new();
+        let mut magnitude = self.digits.clone();
+        while !magnitude.is_empty() {
+            let mut rem = 0u64;
+            for d in magnitude.iter_mut().rev() {
+                let v = (rem << 32) | *d as u64;
+                *d = (v / 1_000_000_000) as u32;
+                rem = v % 1_000_000_000;
+            }
+            groups.push(rem);
+            magnitude = BigInt::new(false, magnitude).digits;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut groups = groups.iter().rev();
+        write!(f, "{}", groups.next().unwrap())?;
+        groups.try_for_each(|g| write!(f, "{:09}", g))
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseBigIntError;
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid integer")
+    }
+}
+
+impl Error for ParseBigIntError {}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<BigInt, ParseBigIntError> {
+        let (negative, decimal) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if decimal.is_empty() || !decimal.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigIntError);
+        }
+        let mut digits: Vec<u32> = Vec::new();
+        for b in decimal.bytes() {
+            let mut carry = (b - b'0') as u64;
+            for d in digits.iter_mut() {
+                let v = *d as u64 * 10 + carry;
+                *d = v as u32;
+                carry = v >> 32;
+            }
+            if carry > 0 {
+                digits.push(carry as u32);
+            }
+        }
+        Ok(BigInt::new(negative, digits))
+    }
+}
+
+// nothing overflows, so every policy gives the exact result
+impl Word for BigInt {
+    fn zero() -> BigInt {
+        BigInt::default()
+    }
+
+    fn one() -> BigInt {
+        BigInt::new(false, vec![1])
+    }
+
+    fn min_value() -> Option<BigInt> {
+        None
+    }
+
+    fn max_value() -> Option<BigInt> {
+        None
+    }
+
+    fn from_i32(v: i32) -> BigInt {
+        BigInt::from_i128(v as i128)
+    }
+
+    fn to_i32(self) -> i32 {
+        match self.to_i128() {
+            Some(v) => v.clamp(i32::MIN as i128, i32::MAX as i128) as i32,
+            None if self.negative => i32::MIN,
+            None => i32::MAX,
+        }
+    }
+
+    fn to_u64(self) -> u64 {
+        match self.to_i128() {
+            Some(v) => v.clamp(0, u64::MAX as i128) as u64,
+            None if self.negative => 0,
+            None => u64::MAX,
+        }
+    }
+
+    fn checked_add(self, other: BigInt) -> Option<BigInt> {
+        Some(self + other)
+    }
+
+    fn checked_sub(self, other: BigInt) -> Option<BigInt> {
+        Some(self - other)
+    }
+
+    fn checked_mul(self, other: BigInt) -> Option<BigInt> {
+        Some(self * other)
+    }
+
+    fn checked_div(self, other: BigInt) -> Option<BigInt> {
+        match other.is_zero() {
+            true => None,
+            false => Some(self / other),
+        }
+    }
+
+    fn checked_rem(self, other: BigInt) -> Option<BigInt> {
+        match other.is_zero() {
+            true => None,
+            false => Some(self % other),
+        }
+    }
+
+    fn wrapping_add(self, other: BigInt) -> BigInt {
+        self + other
+    }
+
+    fn wrapping_mul(self, other: BigInt) -> BigInt {
+        self * other
+    }
+
+    fn saturating_add(self, other: BigInt) -> BigInt {
+        self + other
+    }
+
+    fn saturating_mul(self, other: BigInt) -> BigInt {
+        self * other
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::machine::snapshot::Snapshot;
+    use crate::machine::*;
+
+    fn big(v: i128) -> BigInt {
+        BigInt::from_i128(v)
+    }
+
+    const VALUES: [i128; 12] = [
+        0,
+        1,
+        -1,
+        7,
+        -13,
+        1 << 31,
+        -(1 << 32),
+        (1 << 32) + 5,
+        u64::MAX as i128,
+        -(u64::MAX as i128) * 3,
+        i64::MIN as i128,
+        (1 << 100) + 12345,
+    ];
+
+    #[test]
+    fn test_matches_i128() {
+        for &a in VALUES.iter() {
+            for &b in VALUES.iter() {
+                assert_eq!(a.cmp(&b), big(a).cmp(&big(b)), "{} cmp {}", a, b);
+                assert_eq!(big(a + b), big(a) + big(b), "{} + {}", a, b);
+                assert_eq!(big(a - b), big(a) - big(b), "{} - {}", a, b);
+                assert_eq!(big(a & b), big(a) & big(b), "{} & {}", a, b);
+                assert_eq!(big(a | b), big(a) | big(b), "{} | {}", a, b);
+                if let Some(p) = a.checked_mul(b) {
+                    assert_eq!(big(p), big(a) * big(b), "{} * {}", a, b);
+                }
+                if b != 0 {
+                    assert_eq!(big(a / b), big(a) / big(b), "{} / {}", a, b);
+                    assert_eq!(big(a % b), big(a) % big(b), "{} % {}", a, b);
+                }
+            }
+            assert_eq!(Some(a), big(a).to_i128());
+            assert_eq!(a.to_string(), big(a).to_string());
+            assert_eq!(Ok(big(a)), a.to_string().parse());
+        }
+    }
+
+    #[test]
+    fn test_beyond_i128() {
+        let pow = (0..100).fold(BigInt::one(), |acc, _| acc * big(3));
+        let expected = "515377520732011331036461129765621272702107522001";
+        assert_eq!(expected, pow.to_string());
+        assert_eq!(big(1), pow.clone() % big(8));
+        assert_eq!(pow.clone(), (pow.clone() * pow.clone()) / pow.clone());
+        assert_eq!(i32::MAX, pow.clone().to_i32());
+        assert_eq!(0, (-pow.clone()).to_u64());
+        assert_eq!(None, pow.to_i128());
+        assert_eq!(Err(ParseBigIntError), "12a".parse::<BigInt>());
+        assert_eq!(Err(ParseBigIntError), "-".parse::<BigInt>());
+        assert_eq!(None, big(1).checked_div(BigInt::zero()));
+    }
+
+    #[test]
+    fn test_machine() {
+        // r1 = 3^100
+        let input = [
+            "#ip 5",
+            "seti 1 0 1",
+            "seti 0 0 2",
+            "muli 1 3 1",
+            "addi 2 1 2",
+            "gtri 2 99 3",
+            "addr 3 5 5",
+            "seti 1 0 5",
+        ];
+        for overflow in [Overflow::Wrap, Overflow::Trap] {
+            let mut machine = Machine::<6, BigInt>::parse(&input).unwrap();
+            machine.set_overflow(overflow);
+            machine.run();
+            assert_eq!(MachineState::Halted, machine.state);
+            assert_eq!(
+                "515377520732011331036461129765621272702107522001",
+                machine.registers.get(&1).to_string()
+            );
+        }
+
+        let mut machine = Machine::<6, BigInt>::parse(&input).unwrap();
+        machine.run();
+        let text = machine.snapshot().to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(machine.snapshot(), Snapshot::parse(&lines).unwrap());
+
+        // symbols are unbounded
+        let res = Machine::<6, BigInt>::parse(&["#ip 1", "gtri 0 5 2"]).unwrap();
+        let res = res.explore(&[0], 100, 1);
+        assert_eq!(Some(big(6)), res.halts[0].domains[&0].min);
+        assert_eq!(None, res.halts[0].domains[&0].max);
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::machine::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EdgeKind {
+    Fallthrough,
+    Jump,
+    // `ip += flag` right after a comparison: skip one instruction when the flag is set
+    Taken,
+    NotTaken,
+    // `ip += r` for a register that isn't known to be a flag, assumed to be 0 or 1
+    Computed,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Successor {
+    Block(usize),
+    Halt,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Edge {
+    pub to: Successor,
+    pub kind: EdgeKind,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BasicBlock {
+    pub start: i32,
+    pub end: i32,
+    pub edges: Vec<Edge>,
+    // the block ends with a jump whose targets can't be known statically
+    pub unresolved: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Loop {
+    pub header: usize,
+    pub latches: Vec<usize>,
+    pub body: BTreeSet<usize>,
+}
+
+fn writes(op: &OpCode) -> Option<i32> {
+    op.operands().map(|(_, _, c)| c)
+}
+
+fn is_comparison(op: &OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::GtIr(..)
+            | OpCode::GtRi(..)
+            | OpCode::GtRr(..)
+            | OpCode::EqIr(..)
+            | OpCode::EqRi(..)
+            | OpCode::EqRr(..)
+    )
+}
+
+// the value written to the ip register, when it doesn't depend on the other registers
+fn constant_write(op: &OpCode, i: i32, ip: i32) -> Option<i32> {
+    if let OpCode::Ext(..) = op {
+        return None;
+    }
+    let (a, b, _) = op.operands()?;
+    let (reg_a, reg_b) = op.register_operands();
+    if (reg_a && a != ip) || (reg_b && b != ip) {
+        return None;
+    }
+
+    // the ip is the only register read, so it can live alone in register 0
+    let remap = |x: i32, reg: bool| if reg { 0 } else { x };
+    let single = op.with_operands(remap(a, reg_a), remap(b, reg_b), 0);
+    Some(single.apply(&Registers::<1>::new([i])).get(&0))
+}
+
+// where the instruction at `i` can send the ip, as (target, kind) pairs; targets outside
+// the program halt it
+pub fn flow(program: &[OpCode], i: i32, ip_reg: u8) -> (Vec<(i32, EdgeKind)>, bool) {
+    let ip = ip_reg as i32;
+    let op = &program[i as usize];
+    if writes(op) != Some(ip) {
+        return (vec![(i + 1, EdgeKind::Fallthrough)], false);
+    }
+
+    if let Some(v) = constant_write(op, i, ip) {
+        return (vec![(v.overflowing_add(1).0, EdgeKind::Jump)], false);
+    }
+
+    let relative = match *op {
+        OpCode::AddR(a, b, _) if a == ip => Some(b),
+        OpCode::AddR(a, b, _) if b == ip => Some(a),
+        _ => None,
+    };
+    match relative {
+        Some(r) => {
+            let flag = i > 0 && {
+                let prev = &program[i as usize - 1];
+                is_comparison(prev) && writes(prev) == Some(r)
+            };
+            if flag {
+                (
+                    vec![(i + 1, EdgeKind::NotTaken), (i + 2, EdgeKind::Taken)],
+                    false,
+                )
+            } else {
+                (
+                    vec![(i + 1, EdgeKind::Computed), (i + 2, EdgeKind::Computed)],
+                    true,
+                )
+            }
+        }
+        None => (Vec::new(), true),
+    }
+}
+
+fn dominated(idom: &[Option<usize>], a: usize, b: usize) -> bool {
+    if b != 0 && idom[b].is_none() {
+        return false;
+    }
+    let mut cur = Some(b);
+    while let Some(c) = cur {
+        if c == a {
+            return true;
+        }
+        cur = idom[c];
+    }
+    false
+}
+
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Cfg {
+    pub fn build(program: &[OpCode], ip_reg: u8) -> Cfg {
+        let len = program.len() as i32;
+        let flows: Vec<(Vec<(i32, EdgeKind)>, bool)> =
+            (0..len).map(|i| flow(program, i, ip_reg)).collect();
+
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        for (i, (targets, unresolved)) in flows.iter().enumerate() {
+            let jumps = *unresolved || targets.iter().any(|t| t.1 != EdgeKind::Fallthrough);
+            if jumps {
+                leaders.insert(i as i32 + 1);
+                leaders.extend(targets.iter().map(|t| t.0));
+            }
+        }
+        let leaders: Vec<i32> = leaders.into_iter().filter(|&l| l >= 0 && l < len).collect();
+
+        let ids: BTreeMap<i32, usize> =
+            leaders.iter().enumerate().map(|(id, &l)| (l, id)).collect();
+        let blocks = leaders
+            .iter()
+            .enumerate()
+            .map(|(id, &start)| {
+                let end = leaders.get(id + 1).map_or(len, |&next| next) - 1;
+                let (targets, unresolved) = &flows[end as usize];
+                let edges = targets
+                    .iter()
+                    .map(|&(t, kind)| Edge {
+                        to: ids
+                            .get(&t)
+                            .map_or(Successor::Halt, |&b| Successor::Block(b)),
+                        kind,
+                    })
+                    .collect();
+                BasicBlock {
+                    start,
+                    end,
+                    edges,
+                    unresolved: *unresolved,
+                }
+            })
+            .collect();
+
+        Cfg { blocks }
+    }
+
+    pub fn block_of(&self, ip: i32) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|b| b.start <= ip && ip <= b.end)
+    }
+
+    pub fn successors(&self, block: usize) -> Vec<usize> {
+        let mut res: Vec<usize> = self.blocks[block]
+            .edges
+            .iter()
+            .filter_map(|e| match e.to {
+                Successor::Block(b) => Some(b),
+                Successor::Halt => None,
+            })
+            .collect();
+        res.dedup();
+        res
+    }
+
+    pub fn predecessors(&self, block: usize) -> Vec<usize> {
+        (0..self.blocks.len())
+            .filter(|&b| self.successors(b).contains(&block))
+            .collect()
+    }
+
+    fn reverse_postorder(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.blocks.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![(0, false)];
+        while let Some((b, done)) = stack.pop() {
+            if done {
+                order.push(b);
+                continue;
+            }
+            if visited[b] {
+                continue;
+            }
+            visited[b] = true;
+            stack.push((b, true));
+            for s in self.successors(b).into_iter().rev() {
+                if !visited[s] {
+                    stack.push((s, false));
+                }
+            }
+        }
+        order.reverse();
+        order
+    }
+
+    // immediate dominator of every block, None for the entry and the unreachable blocks
+    pub fn dominators(&self) -> Vec<Option<usize>> {
+        if self.blocks.is_empty() {
+            return Vec::new();
+        }
+        let order = self.reverse_postorder();
+        let mut rank = vec![usize::MAX; self.blocks.len()];
+        for (i, &b) in order.iter().enumerate() {
+            rank[b] = i;
+        }
+
+        let mut idom: Vec<Option<usize>> = vec![None; self.blocks.len()];
+        idom[0] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &b in order.iter().skip(1) {
+                let mut new_idom = None;
+                for p in self.predecessors(b) {
+                    if idom[p].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => p,
+                        Some(other) => {
+                            let (mut x, mut y) = (p, other);
+                            while x != y {
+                                while rank[x] > rank[y] {
+                                    x = idom[x].unwrap();
+                                }
+                                while rank[y] > rank[x] {
+                                    y = idom[y].unwrap();
+                                }
+                            }
+                            x
+                        }
+                    });
+                }
+                if new_idom.is_some() && idom[b] != new_idom {
+                    idom[b] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        idom[0] = None;
+        idom
+    }
+
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        dominated(&self.dominators(), a, b)
+    }
+
+    // natural loops, one per header, found through the edges jumping back to a dominator
+    pub fn loops(&self) -> Vec<Loop> {
+        let idom = self.dominators();
+        let mut loops: BTreeMap<usize, Loop> = BTreeMap::new();
+        for b in 0..self.blocks.len() {
+            for h in self.successors(b) {
+                if !dominated(&idom, h, b) {
+                    continue;
+                }
+                let l = loops.entry(h).or_insert_with(|| Loop {
+                    header: h,
+                    latches: Vec::new(),
+                    body: vec![h].into_iter().collect(),
+                });
+                l.latches.push(b);
+
+                let mut stack = vec![b];
+                while let Some(n) = stack.pop() {
+                    if l.body.insert(n) {
+                        stack.extend(self.predecessors(n));
+                    }
+                }
+            }
+        }
+        loops.into_values().collect()
+    }
+
+    pub fn to_dot(&self, program: &[OpCode]) -> String {
+        let mut res = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+        for (id, b) in self.blocks.iter().enumerate() {
+            let mut label = String::new();
+            for i in b.start..=b.end {
+                write!(label, "{:>3}: {}\\l", i, program[i as usize]).unwrap();
+            }
+            let style = if b.unresolved { ", style=dashed" } else { "" };
+            writeln!(res, "    b{} [label=\"{}\"{}];", id, label, style).unwrap();
+        }
+
+        let mut halts = false;
+        for (id, b) in self.blocks.iter().enumerate() {
+            for e in &b.edges {
+                let to = match e.to {
+                    Successor::Block(t) => format!("b{}", t),
+                    Successor::Halt => {
+                        halts = true;
+                        String::from("halt")
+                    }
+                };
+                let attrs = match e.kind {
+                    EdgeKind::Fallthrough | EdgeKind::Jump => "",
+                    EdgeKind::Taken => " [label=\"true\"]",
+                    EdgeKind::NotTaken => " [label=\"false\"]",
+                    EdgeKind::Computed => " [style=dashed]",
+                };
+                writeln!(res, "    b{} -> {}{};", id, to, attrs).unwrap();
+            }
+        }
+        if halts {
+            res.push_str("    halt [shape=doublecircle];\n");
+        }
+        res.push_str("}\n");
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn machine(input: &str) -> Machine<6> {
+        let input: Vec<&str> = input.lines().collect();
+        Machine::parse(&input).unwrap()
+    }
+
+    const DIVISORS: &str = "#ip 5
+seti 1 5 3
+seti 1 4 2
+mulr 3 2 4
+eqrr 4 1 4
+addr 4 5 5
+addi 5 1 5
+addr 3 0 0
+addi 2 1 2
+gtrr 2 1 4
+addr 5 4 5
+seti 1 2 5
+addi 3 1 3
+gtrr 3 1 4
+addr 4 5 5
+seti 0 0 5
+mulr 5 5 5";
+
+    #[test]
+    fn test_flow() {
+        let m = machine(DIVISORS);
+        let program = m.program();
+        assert_eq!(
+            (vec![(1, EdgeKind::Fallthrough)], false),
+            flow(program, 0, 5)
+        );
+        assert_eq!(
+            (vec![(5, EdgeKind::NotTaken), (6, EdgeKind::Taken)], false),
+            flow(program, 4, 5)
+        );
+        assert_eq!((vec![(7, EdgeKind::Jump)], false), flow(program, 5, 5));
+        assert_eq!((vec![(226, EdgeKind::Jump)], false), flow(program, 15, 5));
+
+        let computed = machine("#ip 1\naddr 1 0 1\nmulr 0 1 1");
+        assert_eq!(
+            (vec![(1, EdgeKind::Computed), (2, EdgeKind::Computed)], true),
+            flow(computed.program(), 0, 1)
+        );
+        assert_eq!((vec![], true), flow(computed.program(), 1, 1));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let m = machine(DIVISORS);
+        let cfg = Cfg::build(m.program(), m.ip_reg);
+        let starts: Vec<i32> = cfg.blocks.iter().map(|b| b.start).collect();
+        assert_eq!(vec![0, 1, 2, 5, 6, 7, 10, 11, 14, 15], starts);
+        assert_eq!(Some(3), cfg.block_of(5));
+        assert_eq!(vec![3, 4], cfg.predecessors(5));
+        assert_eq!(
+            vec![Edge {
+                to: Successor::Halt,
+                kind: EdgeKind::Jump
+            }],
+            cfg.blocks[9].edges
+        );
+    }
+
+    #[test]
+    fn test_dominators() {
+        let m = machine(DIVISORS);
+        let cfg = Cfg::build(m.program(), m.ip_reg);
+        assert_eq!(
+            vec![
+                None,
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(2),
+                Some(2),
+                Some(5),
+                Some(5),
+                Some(7),
+                Some(7)
+            ],
+            cfg.dominators()
+        );
+        assert!(cfg.dominates(1, 6));
+        assert!(!cfg.dominates(3, 5));
+    }
+
+    #[test]
+    fn test_loops() {
+        let m = machine(DIVISORS);
+        let cfg = Cfg::build(m.program(), m.ip_reg);
+        let loops = cfg.loops();
+        assert_eq!(2, loops.len());
+        assert_eq!(1, loops[0].header);
+        assert_eq!(vec![8], loops[0].latches);
+        assert_eq!((1..=8).collect::<BTreeSet<_>>(), loops[0].body);
+        assert_eq!(2, loops[1].header);
+        assert_eq!(vec![6], loops[1].latches);
+        assert_eq!((2..=6).collect::<BTreeSet<_>>(), loops[1].body);
+    }
+
+    #[test]
+    fn test_dot() {
+        let m = machine(DIVISORS);
+        let dot = Cfg::build(m.program(), m.ip_reg).to_dot(m.program());
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("    b0 [label=\"  0: seti 1 5 3\\l\"];\n"));
+        assert!(dot.contains("    b2 -> b3 [label=\"false\"];\n"));
+        assert!(dot.contains("    b9 -> halt;\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
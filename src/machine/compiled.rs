@@ -0,0 +1,221 @@
+use crate::machine::optimizer::Idiom;
+use crate::machine::*;
+
+// false when the instruction traps on an overflow
+type Op<const N: usize, W> = Box<dyn Fn(&mut [W; N]) -> bool>;
+
+// out of range registers read as 0 and ignore writes, like in `Registers`
+#[derive(Clone, Copy)]
+enum Src<W> {
+    Reg(usize),
+    Imm(W),
+}
+
+fn src<const N: usize, W: Word>(x: i32, register: bool) -> Src<W> {
+    match register {
+        true if x >= 0 && (x as usize) < N => Src::Reg(x as usize),
+        true => Src::Imm(W::zero()),
+        false => Src::Imm(W::from_i32(x)),
+    }
+}
+
+fn bin<const N: usize, W: Word, F>(a: Src<W>, b: Src<W>, c: usize, f: F) -> Op<N, W>
+where
+    F: Fn(W, W) -> Option<W> + 'static,
+{
+    let store = move |r: &mut [W; N], v: Option<W>| match v {
+        Some(v) => {
+            r[c] = v;
+            true
+        }
+        None => false,
+    };
+    match (a, b) {
+        (Src::Reg(a), Src::Reg(b)) => Box::new(move |r| store(r, f(r[a].clone(), r[b].clone()))),
+        (Src::Reg(a), Src::Imm(b)) => Box::new(move |r| store(r, f(r[a].clone(), b.clone()))),
+        (Src::Imm(a), Src::Reg(b)) => Box::new(move |r| store(r, f(a.clone(), r[b].clone()))),
+        (Src::Imm(a), Src::Imm(b)) => {
+            let v = f(a, b);
+            Box::new(move |r| store(r, v.clone()))
+        }
+    }
+}
+
+fn flag<W: Word>(v: bool) -> Option<W> {
+    Some(if v { W::one() } else { W::zero() })
+}
+
+fn compile_op<const N: usize, W: Word>(op: &OpCode, overflow: Overflow) -> Op<N, W> {
+    let (a, b, c) = match op.operands() {
+        Some((a, b, c)) if c >= 0 && (c as usize) < N => (a, b, c as usize),
+        _ => return Box::new(|_| true),
+    };
+    let (reg_a, reg_b) = op.register_operands();
+    let (a, b) = (src::<N, W>(a, reg_a), src::<N, W>(b, reg_b));
+
+    match op {
+        OpCode::AddR(..) | OpCode::AddI(..) => match overflow {
+            Overflow::Wrap => bin(a, b, c, |x: W, y| Some(x.wrapping_add(y))),
+            Overflow::Saturate => bin(a, b, c, |x: W, y| Some(x.saturating_add(y))),
+            Overflow::Trap => bin(a, b, c, |x: W, y| x.checked_add(y)),
+        },
+        OpCode::MulR(..) | OpCode::MulI(..) => match overflow {
+            Overflow::Wrap => bin(a, b, c, |x: W, y| Some(x.wrapping_mul(y))),
+            Overflow::Saturate => bin(a, b, c, |x: W, y| Some(x.saturating_mul(y))),
+            Overflow::Trap => bin(a, b, c, |x: W, y| x.checked_mul(y)),
+        },
+        OpCode::BanR(..) | OpCode::BanI(..) => bin(a, b, c, |x, y| Some(x & y)),
+        OpCode::BorR(..) | OpCode::BorI(..) => bin(a, b, c, |x, y| Some(x | y)),
+        OpCode::SetR(..) | OpCode::SetI(..) => bin(a, b, c, |x, _| Some(x)),
+        OpCode::GtIr(..) | OpCode::GtRi(..) | OpCode::GtRr(..) => bin(a, b, c, |x, y| flag(x > y)),
+        OpCode::EqIr(..) | OpCode::EqRi(..) | OpCode::EqRr(..) => bin(a, b, c, |x, y| flag(x == y)),
+        // executed by the machine, which knows the registry
+        OpCode::Ext(..) | OpCode::Nop => Box::new(|_| true),
+    }
+}
+
+// the program translated to one closure per instruction, working on the registers in place
+pub struct Compiled<const N: usize, W> {
+    ops: Vec<Op<N, W>>,
+}
+
+impl<const N: usize, W: Word> Compiled<N, W> {
+    pub fn new(program: &[OpCode], overflow: Overflow) -> Compiled<N, W> {
+        Compiled {
+            ops: program.iter().map(|op| compile_op(op, overflow)).collect(),
+        }
+    }
+
+    // executes the instruction at `ip` without touching the ip register, false if it traps;
+    // there must be one
+    pub fn exec(&self, ip: i32, registers: &mut Registers<N, W>) -> bool {
+        self.ops[ip as usize](&mut registers.inner)
+    }
+
+    // runs until the ip leaves the program, an instruction traps, `steps` reaches `limit`
+    // or, after the first instruction, the ip reaches one flagged in `stops`; the machine is
+    // still active in the last two cases
+    pub fn run(
+        &self,
+        ip_reg: usize,
+        registers: &mut Registers<N, W>,
+        accelerators: &[Option<Idiom>],
+        stops: &[bool],
+        steps: &mut u64,
+        limit: u64,
+    ) -> MachineState {
+        let regs = &mut registers.inner;
+        let start = *steps;
+        while *steps < limit {
+            let ip = regs[ip_reg].clone().to_i32();
+            if *steps > start && stops.get(ip as usize) == Some(&true) {
+                break;
+            }
+            if let Some(Some(idiom)) = accelerators.get(ip as usize) {
+                let mut wrapped = Registers::new(regs.clone());
+                if let Some(executed) = idiom.apply(&mut wrapped, &(ip_reg as i32)) {
+                    *regs = wrapped.inner;
+                    *steps += executed;
+                    continue;
+                }
+            }
+            match self.ops.get(ip as usize) {
+                Some(op) => {
+                    if !op(regs) {
+                        return MachineState::Faulted(Fault::Overflow(ip));
+                    }
+                    regs[ip_reg] = regs[ip_reg].clone().wrapping_add(W::one());
+                    *steps += 1;
+                }
+                None => return MachineState::Halted,
+            }
+        }
+        MachineState::Active
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run<const N: usize>(input: &[&str], engine: Engine, initial: [i32; N]) -> [i32; N] {
+        let mut machine = Machine::<N>::parse(input).unwrap();
+        machine.registers = Registers::new(initial);
+        machine.set_engine(engine);
+        machine.run();
+        machine.registers.all()
+    }
+
+    #[test]
+    fn test_matches_interpreter() {
+        let input = [
+            "#ip 0",
+            "seti 5 0 1",
+            "seti 6 0 2",
+            "addi 0 1 0",
+            "addr 1 2 3",
+            "setr 1 0 0",
+            "seti 8 0 0",
+            "seti 9 0 0",
+            "seti 7 0 3",
+        ];
+        assert_eq!(
+            run(&input, Engine::Interpreter, [0; 6]),
+            run(&input, Engine::Compiled, [0; 6])
+        );
+        assert_eq!([10, 5, 6, 0, 0, 0], run(&input, Engine::Compiled, [0; 6]));
+    }
+
+    #[test]
+    fn test_every_opcode() {
+        let ops = [
+            "addr", "addi", "mulr", "muli", "banr", "bani", "borr", "bori", "setr", "seti", "gtir",
+            "gtri", "gtrr", "eqir", "eqri", "eqrr",
+        ];
+        let initial = [3, 2, 1, 7];
+        for op in ops.iter() {
+            for (a, b, c) in [(2, 1, 3), (3, 3, 0), (0, 2, 2), (7, 1, 1), (1, 9, 9)] {
+                let line = format!("{} {} {} {}", op, a, b, c);
+                let input = ["#ip 9", line.as_str()];
+                let mut interpreted = Machine::<4>::parse(&input).unwrap();
+                interpreted.registers = Registers::new(initial);
+                interpreted.execute_step();
+
+                let mut compiled = Machine::<4>::parse(&input).unwrap();
+                compiled.registers = Registers::new(initial);
+                compiled.set_engine(Engine::Compiled);
+                compiled.execute_step();
+
+                assert_eq!(interpreted.registers, compiled.registers, "{}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn test_accelerated() {
+        let input = [
+            "#ip 5",
+            "seti 1 5 3",
+            "seti 1 4 2",
+            "mulr 3 2 4",
+            "eqrr 4 1 4",
+            "addr 4 5 5",
+            "addi 5 1 5",
+            "addr 3 0 0",
+            "addi 2 1 2",
+            "gtrr 2 1 4",
+            "addr 5 4 5",
+            "seti 1 2 5",
+            "addi 3 1 3",
+            "gtrr 3 1 4",
+            "addr 4 5 5",
+            "seti 0 0 5",
+        ];
+        let mut machine = Machine::<6>::parse(&input).unwrap();
+        machine.registers.set(&1, 10_000);
+        machine.optimize();
+        machine.set_engine(Engine::Compiled);
+        machine.run();
+        assert_eq!(24_211, machine.registers.get(&0));
+    }
+}
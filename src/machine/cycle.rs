@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+use std::mem;
+
+use crate::machine::*;
+
+// a sequence of observations x0, x1, ... in which x(start + length) == x(start)
+#[derive(PartialEq, Eq, Debug)]
+pub struct Cycle<T> {
+    pub start: usize,
+    pub length: usize,
+    // everything observed before the first repetition
+    pub values: Vec<T>,
+}
+
+// observations are taken every time the machine is about to execute the instruction at
+// `ip`, starting from the current one if it's there already; they must determine the rest
+// of the run, which the full registers always do. Brent's algorithm needs memory only for
+// a couple of states, replaying the machine from the start to find where the cycle begins.
+// The machine is left as it was, step counter and I/O included. Returns None if it stops
+// first, never returns if it runs forever without repeating itself
+impl<const N: usize, W: Word> Machine<N, W> {
+    pub fn find_cycle(&mut self, ip: i32) -> Option<Cycle<Registers<N, W>>> {
+        self.find_cycle_by(ip, |r| r.clone())
+    }
+
+    pub fn find_cycle_by<T, F>(&mut self, ip: i32, projection: F) -> Option<Cycle<T>>
+    where
+        T: PartialEq,
+        F: Fn(&Registers<N, W>) -> T,
+    {
+        // the replays are exploratory, they neither count as steps nor get traced
+        let (registers, state, steps) = (self.registers.clone(), self.state, self.steps);
+        let io = self.io.clone();
+        let (tracer, profile) = (self.tracer.take(), self.profile.take());
+        let res = self.brent(ip, &projection);
+        self.registers = registers;
+        self.state = state;
+        self.steps = steps;
+        self.io = io;
+        self.tracer = tracer;
+        self.profile = profile;
+        res
+    }
+
+    fn brent<T, F>(&mut self, ip: i32, projection: &F) -> Option<Cycle<T>>
+    where
+        T: PartialEq,
+        F: Fn(&Registers<N, W>) -> T,
+    {
+        let first = match self.state == MachineState::Active && self.curr_ip() == ip {
+            true => self.registers.clone(),
+            false => self.next_at(ip, &self.registers.clone())?,
+        };
+        let observe = |r: Registers<N, W>| {
+            let v = projection(&r);
+            (r, v)
+        };
+
+        // the tortoise jumps to the hare at every power of two, until the hare meets it
+        // again: the distance is then the length of the cycle
+        let mut power = 1;
+        let mut length = 1;
+        let mut tortoise = projection(&first);
+        let mut hare = observe(self.next_at(ip, &first)?);
+        while hare.1 != tortoise {
+            if power == length {
+                tortoise = projection(&hare.0);
+                power *= 2;
+                length = 0;
+            }
+            hare = observe(self.next_at(ip, &hare.0)?);
+            length += 1;
+        }
+
+        // with the hare `length` ahead, both meet where the cycle starts
+        let mut hare = first.clone();
+        for _ in 0..length {
+            hare = self.next_at(ip, &hare)?;
+        }
+        let mut hare = observe(hare);
+        let mut tortoise = observe(first);
+        let mut values = Vec::new();
+        while tortoise.1 != hare.1 {
+            hare = observe(self.next_at(ip, &hare.0)?);
+            let next = observe(self.next_at(ip, &tortoise.0)?);
+            values.push(mem::replace(&mut tortoise, next).1);
+        }
+
+        let start = values.len();
+        for _ in 0..length {
+            let next = observe(self.next_at(ip, &tortoise.0)?);
+            values.push(mem::replace(&mut tortoise, next).1);
+        }
+        Some(Cycle {
+            start,
+            length,
+            values,
+        })
+    }
+
+    // the registers the next time the machine reaches `ip`, starting from `registers`
+    fn next_at(&mut self, ip: i32, registers: &Registers<N, W>) -> Option<Registers<N, W>> {
+        self.registers = registers.clone();
+        self.state = MachineState::Active;
+        let stops: HashSet<i32> = [ip].iter().copied().collect();
+        self.run_to(u64::MAX, &stops);
+        match self.state {
+            MachineState::Active => Some(self.registers.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    // x = (x * x + 1) & 255, observed at ip 1
+    const PROGRAM: [&str; 6] = [
+        "#ip 5",
+        "seti 3 0 1",
+        "mulr 1 1 1",
+        "addi 1 1 1",
+        "bani 1 255 1",
+        "seti 0 0 5",
+    ];
+
+    fn naive() -> Cycle<i32> {
+        let mut seen = HashMap::new();
+        let mut values = Vec::new();
+        let mut x = 3;
+        while !seen.contains_key(&x) {
+            seen.insert(x, values.len());
+            values.push(x);
+            x = (x * x + 1) & 255;
+        }
+        Cycle {
+            start: seen[&x],
+            length: values.len() - seen[&x],
+            values,
+        }
+    }
+
+    #[test]
+    fn test_projection() {
+        let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+        machine.enable_profiling();
+        let cycle = machine.find_cycle_by(1, |r| r.get(&1)).unwrap();
+        assert_eq!(naive(), cycle);
+        assert!(cycle.start > 0 && cycle.length > 1);
+        assert_eq!([0; 6], machine.registers.all());
+        assert_eq!(0, machine.steps());
+        assert_eq!(Some(0), machine.profile().map(|p| p.steps()));
+    }
+
+    #[test]
+    fn test_full_state() {
+        let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+        machine.optimize();
+        machine.set_engine(Engine::Compiled);
+        let cycle = machine.find_cycle(1).unwrap();
+        let expected = naive();
+        assert_eq!(
+            (expected.start, expected.length),
+            (cycle.start, cycle.length)
+        );
+        assert_eq!(
+            expected.values,
+            cycle.values.iter().map(|r| r.get(&1)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_halts() {
+        let input = [
+            "#ip 5",
+            "seti 0 0 1",
+            "addi 1 1 1",
+            "gtri 1 9 2",
+            "addr 5 2 5",
+            "seti 0 0 5",
+        ];
+        let mut machine = Machine::<6>::parse(&input).unwrap();
+        assert_eq!(None, machine.find_cycle(1));
+        assert_eq!(MachineState::Active, machine.state);
+        assert_eq!([0; 6], machine.registers.all());
+    }
+}
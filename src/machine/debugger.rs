@@ -0,0 +1,335 @@
+use std::fmt;
+use std::mem;
+
+use crate::machine::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Watchpoint {
+    // triggers whenever the register changes value
+    Write(i32),
+    // triggers when the register takes the given value
+    Value(i32, i32),
+}
+
+impl Watchpoint {
+    fn register(&self) -> i32 {
+        match *self {
+            Watchpoint::Write(r) | Watchpoint::Value(r, _) => r,
+        }
+    }
+
+    fn triggered<const N: usize, W: Word>(
+        &self,
+        before: &Registers<N, W>,
+        after: &Registers<N, W>,
+    ) -> bool {
+        let r = self.register();
+        match *self {
+            Watchpoint::Write(_) => before.get(&r) != after.get(&r),
+            Watchpoint::Value(_, v) => {
+                let v = W::from_i32(v);
+                before.get(&r) != v && after.get(&r) == v
+            }
+        }
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Watchpoint::Write(r) => write!(f, "r{}", r),
+            Watchpoint::Value(r, v) => write!(f, "r{} == {}", r, v),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StopReason {
+    Breakpoint(i32),
+    Watchpoint(Watchpoint),
+    Halted,
+    Faulted(Fault),
+    Condition,
+    StepsDone,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::Breakpoint(ip) => write!(f, "breakpoint at {}", ip),
+            StopReason::Watchpoint(w) => write!(f, "watchpoint {}", w),
+            StopReason::Halted => write!(f, "halted"),
+            StopReason::Faulted(fault) => write!(f, "faulted: {}", fault),
+            StopReason::Condition => write!(f, "condition met"),
+            StopReason::StepsDone => write!(f, "stepped"),
+        }
+    }
+}
+
+// breakpoints stop the machine right before the instruction at their ip is executed, the
+// first one included, so an accelerated loop (see `optimize`) only stops at its first
+// instruction; going on from a breakpoint executes that instruction
+impl<const N: usize, W: Word> Machine<N, W> {
+    pub fn add_breakpoint(&mut self, ip: i32) {
+        self.breakpoints.insert(ip);
+    }
+
+    pub fn remove_breakpoint(&mut self, ip: i32) -> bool {
+        self.breakpoints.remove(&ip)
+    }
+
+    pub fn breakpoints(&self) -> Vec<i32> {
+        let mut res: Vec<i32> = self.breakpoints.iter().copied().collect();
+        res.sort_unstable();
+        res
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    // removes every watchpoint on the given register
+    pub fn remove_watchpoints(&mut self, register: i32) -> bool {
+        let len = self.watchpoints.len();
+        self.watchpoints.retain(|w| w.register() != register);
+        len != self.watchpoints.len()
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    // executes up to `n` instructions, stopping early on breakpoints and watchpoints
+    pub fn step(&mut self, n: usize) -> StopReason {
+        for _ in 0..n {
+            if let Some(reason) = self.debug_step() {
+                return reason;
+            }
+        }
+        StopReason::StepsDone
+    }
+
+    pub fn resume(&mut self) -> StopReason {
+        self.run_for(u64::MAX)
+    }
+
+    // executes up to `steps` instructions like `step`, taking the fast path of `run`, compiled
+    // engine included, unless there are watchpoints
+    pub fn run_for(&mut self, steps: u64) -> StopReason {
+        if let Some(reason) = self.stopped().or_else(|| self.at_breakpoint()) {
+            return reason;
+        }
+        let limit = self.steps.saturating_add(steps);
+        if self.watchpoints.is_empty() {
+            let breakpoints = mem::take(&mut self.breakpoints);
+            self.run_to(limit, &breakpoints);
+            self.breakpoints = breakpoints;
+        } else {
+            while self.steps < limit {
+                if let Some(reason) = self.debug_step() {
+                    return reason;
+                }
+            }
+        }
+
+        self.stopped()
+            .or_else(|| self.at_breakpoint())
+            .unwrap_or(StopReason::StepsDone)
+    }
+
+    // like `resume`, also stopping after any instruction that makes the predicate true; a
+    // budget can be set by checking `steps`
+    pub fn run_until<F>(&mut self, mut predicate: F) -> StopReason
+    where
+        F: FnMut(&Machine<N, W>) -> bool,
+    {
+        loop {
+            if let Some(reason) = self.debug_step() {
+                return reason;
+            }
+            if predicate(self) {
+                return StopReason::Condition;
+            }
+        }
+    }
+
+    // why the machine can't go on, if it can't
+    pub fn stopped(&self) -> Option<StopReason> {
+        match self.state {
+            MachineState::Active => None,
+            MachineState::Halted => Some(StopReason::Halted),
+            MachineState::Faulted(fault) => Some(StopReason::Faulted(fault)),
+        }
+    }
+
+    // a breakpoint at the current ip, unless it was already reported at this step
+    fn at_breakpoint(&mut self) -> Option<StopReason> {
+        let ip = self.curr_ip();
+        if !self.breakpoints.contains(&ip) || self.paused == Some((self.steps, ip)) {
+            return None;
+        }
+        self.paused = Some((self.steps, ip));
+        Some(StopReason::Breakpoint(ip))
+    }
+
+    fn debug_step(&mut self) -> Option<StopReason> {
+        if let Some(reason) = self.stopped().or_else(|| self.at_breakpoint()) {
+            return Some(reason);
+        }
+
+        let before = if self.watchpoints.is_empty() {
+            None
+        } else {
+            Some(self.registers.clone())
+        };
+        self.execute_step();
+        if let Some(reason) = self.stopped() {
+            return Some(reason);
+        }
+
+        if let Some(before) = before {
+            if let Some(w) = self
+                .watchpoints
+                .iter()
+                .find(|w| w.triggered(&before, &self.registers))
+            {
+                return Some(StopReason::Watchpoint(*w));
+            }
+        }
+
+        self.at_breakpoint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROGRAM: [&str; 9] = [
+        "#ip 0",
+        "seti 5 0 1",
+        "seti 6 0 2",
+        "addr 1 2 3",
+        "addi 3 1 3",
+        "addi 4 1 4",
+        "gtri 4 2 5",
+        "addr 0 5 0",
+        "seti 3 0 0",
+    ];
+
+    #[test]
+    fn test_breakpoint() {
+        for &engine in [Engine::Interpreter, Engine::Compiled].iter() {
+            let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+            machine.set_engine(engine);
+            machine.add_breakpoint(4);
+            assert_eq!(StopReason::Breakpoint(4), machine.resume());
+            assert_eq!([4, 5, 6, 12, 0, 0], machine.registers.all());
+            assert_eq!(StopReason::Breakpoint(4), machine.resume());
+            assert_eq!([4, 5, 6, 12, 1, 0], machine.registers.all());
+
+            assert!(machine.remove_breakpoint(4));
+            assert!(!machine.remove_breakpoint(4));
+            assert_eq!(StopReason::Halted, machine.resume());
+            assert_eq!(3, machine.registers.get(&4));
+            assert_eq!(StopReason::Halted, machine.step(1));
+        }
+    }
+
+    #[test]
+    fn test_starting_breakpoint() {
+        for &engine in [Engine::Interpreter, Engine::Compiled].iter() {
+            let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+            machine.set_engine(engine);
+            machine.add_breakpoint(0);
+            machine.add_breakpoint(4);
+            assert_eq!(StopReason::Breakpoint(0), machine.resume());
+            assert_eq!(0, machine.steps());
+            assert_eq!(StopReason::Breakpoint(4), machine.resume());
+            assert_eq!(4, machine.steps());
+            assert_eq!(StopReason::StepsDone, machine.step(1));
+            assert_eq!(StopReason::Breakpoint(4), machine.run_for(100));
+            assert_eq!(8, machine.steps());
+
+            // a breakpoint reached by stepping isn't reported again when going on
+            machine.add_breakpoint(5);
+            assert_eq!(StopReason::Breakpoint(5), machine.step(1));
+            assert_eq!(StopReason::Breakpoint(4), machine.resume());
+            assert_eq!(12, machine.steps());
+        }
+    }
+
+    #[test]
+    fn test_step() {
+        let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+        assert_eq!(StopReason::StepsDone, machine.step(2));
+        assert_eq!(2, machine.curr_ip());
+        machine.add_breakpoint(5);
+        assert_eq!(StopReason::StepsDone, machine.step(2));
+        assert_eq!(StopReason::Breakpoint(5), machine.step(10));
+        assert_eq!(vec![5], machine.breakpoints());
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+        machine.add_watchpoint(Watchpoint::Write(3));
+        machine.add_watchpoint(Watchpoint::Value(2, 6));
+        assert_eq!(
+            StopReason::Watchpoint(Watchpoint::Value(2, 6)),
+            machine.resume()
+        );
+        assert_eq!(
+            StopReason::Watchpoint(Watchpoint::Write(3)),
+            machine.resume()
+        );
+        assert_eq!(11, machine.registers.get(&3));
+
+        assert!(machine.remove_watchpoints(3));
+        assert_eq!(StopReason::Halted, machine.resume());
+    }
+
+    #[test]
+    fn test_fault() {
+        let mut machine = Machine::<6>::parse(&["#ip 5", "seti 1 0 1", "eqrr 1 9 2"]).unwrap();
+        let fault = StopReason::Faulted(Fault::InvalidRegister(1, 9));
+        assert_eq!(fault, machine.resume());
+        assert_eq!(fault, machine.step(1));
+        assert_eq!("faulted: invalid register 9 at 1", fault.to_string());
+    }
+
+    #[test]
+    fn test_run_for() {
+        for &engine in [Engine::Interpreter, Engine::Compiled].iter() {
+            let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+            machine.set_engine(engine);
+            assert_eq!(StopReason::StepsDone, machine.run_for(7));
+            assert_eq!(7, machine.steps());
+            assert_eq!([7, 5, 6, 12, 1, 0], machine.registers.all());
+
+            machine.add_breakpoint(4);
+            assert_eq!(StopReason::Breakpoint(4), machine.run_for(100));
+            assert_eq!(8, machine.steps());
+            assert!(machine.remove_breakpoint(4));
+            assert_eq!(StopReason::Halted, machine.run_for(100));
+            assert_eq!(15, machine.steps());
+            assert_eq!(StopReason::Halted, machine.run_for(100));
+            assert_eq!(15, machine.steps());
+        }
+    }
+
+    #[test]
+    fn test_run_until() {
+        let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+        let reason = machine.run_until(|m| m.registers.get(&4) == 2);
+        assert_eq!(StopReason::Condition, reason);
+        assert_eq!([5, 5, 6, 12, 2, 0], machine.registers.all());
+
+        let reason = machine.run_until(|m| m.steps() >= 15);
+        assert_eq!(StopReason::Condition, reason);
+        assert_eq!(15, machine.steps());
+        assert_eq!(StopReason::Halted, machine.run_until(|_| false));
+    }
+}
@@ -0,0 +1,652 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::machine::cfg::{Cfg, EdgeKind, Successor};
+use crate::machine::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinOp {
+    Add,
+    Mul,
+    And,
+    Or,
+    Gt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl BinOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Mul => "*",
+            BinOp::And => "&",
+            BinOp::Or => "|",
+            BinOp::Gt => ">",
+            BinOp::Le => "<=",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+        }
+    }
+
+    // None when an addition or multiplication traps
+    pub(super) fn eval<W: Word>(&self, a: W, b: W, overflow: Overflow) -> Option<W> {
+        let truth = |t: bool| if t { W::one() } else { W::zero() };
+        match self {
+            BinOp::Add => a.add_with(b, overflow),
+            BinOp::Mul => a.mul_with(b, overflow),
+            BinOp::And => Some(a & b),
+            BinOp::Or => Some(a | b),
+            BinOp::Gt => Some(truth(a > b)),
+            BinOp::Le => Some(truth(a <= b)),
+            BinOp::Eq => Some(truth(a == b)),
+            BinOp::Ne => Some(truth(a != b)),
+        }
+    }
+}
+
+// the constants are words of the machine, i32 for decompiled programs
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Expr<W = i32> {
+    Reg(i32),
+    Const(W),
+    Bin(Box<Expr<W>>, BinOp, Box<Expr<W>>),
+    Not(Box<Expr<W>>),
+}
+
+impl<W: Word> Expr<W> {
+    pub(super) fn negate(self) -> Expr<W> {
+        match self {
+            Expr::Bin(a, BinOp::Gt, b) => Expr::Bin(a, BinOp::Le, b),
+            Expr::Bin(a, BinOp::Le, b) => Expr::Bin(a, BinOp::Gt, b),
+            Expr::Bin(a, BinOp::Eq, b) => Expr::Bin(a, BinOp::Ne, b),
+            Expr::Bin(a, BinOp::Ne, b) => Expr::Bin(a, BinOp::Eq, b),
+            Expr::Not(e) => *e,
+            e => Expr::Not(Box::new(e)),
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Bin(_, _, _) => write!(f, "({})", self),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+impl Expr {
+    // folds constants with wrapping arithmetic, like the default overflow policy
+    pub(super) fn bin(a: Expr, op: BinOp, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) => Expr::Const(op.eval(a, b, Overflow::Wrap).unwrap()),
+            (a, b) => Expr::Bin(Box::new(a), op, Box::new(b)),
+        }
+    }
+
+    // None when folding k into a constant overflows
+    fn offset(self, k: i32) -> Option<Expr> {
+        match self {
+            Expr::Const(v) => v.checked_add(k).map(Expr::Const),
+            Expr::Bin(a, BinOp::Add, b) => match (*a, *b) {
+                (a, Expr::Const(v)) | (Expr::Const(v), a) => {
+                    let v = v.checked_add(k)?;
+                    Some(Expr::bin(a, BinOp::Add, Expr::Const(v)))
+                }
+                (a, b) => Some(Expr::bin(
+                    Expr::bin(a, BinOp::Add, b),
+                    BinOp::Add,
+                    Expr::Const(k),
+                )),
+            },
+            e => Some(Expr::bin(e, BinOp::Add, Expr::Const(k))),
+        }
+    }
+
+    fn is_condition(&self) -> bool {
+        match self {
+            Expr::Bin(_, op, _) => matches!(op, BinOp::Gt | BinOp::Le | BinOp::Eq | BinOp::Ne),
+            Expr::Not(_) => true,
+            _ => false,
+        }
+    }
+
+    fn reads(&self, r: i32) -> bool {
+        match self {
+            Expr::Reg(x) => *x == r,
+            Expr::Const(_) => false,
+            Expr::Bin(a, _, b) => a.reads(r) || b.reads(r),
+            Expr::Not(e) => e.reads(r),
+        }
+    }
+
+    fn substitute(&self, r: i32, with: &Expr) -> Expr {
+        match self {
+            Expr::Reg(x) if *x == r => with.clone(),
+            Expr::Bin(a, op, b) => Expr::bin(a.substitute(r, with), *op, b.substitute(r, with)),
+            Expr::Not(e) => Expr::Not(Box::new(e.substitute(r, with))),
+            e => e.clone(),
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Expr<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Reg(r) => write!(f, "r{}", r),
+            Expr::Const(v) => write!(f, "{}", v),
+            Expr::Bin(a, op, b) => {
+                a.fmt_operand(f)?;
+                write!(f, " {} ", op.symbol())?;
+                b.fmt_operand(f)
+            }
+            Expr::Not(e) => {
+                write!(f, "!")?;
+                e.fmt_operand(f)
+            }
+        }
+    }
+}
+
+// jump targets are instruction indexes, anything outside the program halts it
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Stmt {
+    Assign(i32, Expr),
+    Goto(i32),
+    If(Expr, i32),
+    ComputedGoto(Expr),
+    // a custom instruction, opaque to the decompiler
+    Call(OpCode),
+}
+
+impl Stmt {
+    fn target(&self) -> Option<i32> {
+        match self {
+            Stmt::Goto(t) | Stmt::If(_, t) => Some(*t),
+            _ => None,
+        }
+    }
+}
+
+fn lift_op(op: &OpCode, i: i32, ip: i32) -> Option<Stmt> {
+    let reg = |r: i32| {
+        if r == ip {
+            Expr::Const(i)
+        } else {
+            Expr::Reg(r)
+        }
+    };
+    let imm = Expr::Const;
+
+    let (expr, c) = match *op {
+        OpCode::AddR(a, b, c) => (Expr::bin(reg(a), BinOp::Add, reg(b)), c),
+        OpCode::AddI(a, b, c) => (Expr::bin(reg(a), BinOp::Add, imm(b)), c),
+        OpCode::MulR(a, b, c) => (Expr::bin(reg(a), BinOp::Mul, reg(b)), c),
+        OpCode::MulI(a, b, c) => (Expr::bin(reg(a), BinOp::Mul, imm(b)), c),
+        OpCode::BanR(a, b, c) => (Expr::bin(reg(a), BinOp::And, reg(b)), c),
+        OpCode::BanI(a, b, c) => (Expr::bin(reg(a), BinOp::And, imm(b)), c),
+        OpCode::BorR(a, b, c) => (Expr::bin(reg(a), BinOp::Or, reg(b)), c),
+        OpCode::BorI(a, b, c) => (Expr::bin(reg(a), BinOp::Or, imm(b)), c),
+        OpCode::SetR(a, _, c) => (reg(a), c),
+        OpCode::SetI(a, _, c) => (imm(a), c),
+        OpCode::GtIr(a, b, c) => (Expr::bin(imm(a), BinOp::Gt, reg(b)), c),
+        OpCode::GtRi(a, b, c) => (Expr::bin(reg(a), BinOp::Gt, imm(b)), c),
+        OpCode::GtRr(a, b, c) => (Expr::bin(reg(a), BinOp::Gt, reg(b)), c),
+        OpCode::EqIr(a, b, c) => (Expr::bin(imm(a), BinOp::Eq, reg(b)), c),
+        OpCode::EqRi(a, b, c) => (Expr::bin(reg(a), BinOp::Eq, imm(b)), c),
+        OpCode::EqRr(a, b, c) => (Expr::bin(reg(a), BinOp::Eq, reg(b)), c),
+        OpCode::Ext(..) => return Some(Stmt::Call(*op)),
+        OpCode::Nop => return None,
+    };
+
+    if c != ip {
+        return Some(Stmt::Assign(c, expr));
+    }
+    match expr.clone().offset(1) {
+        Some(Expr::Const(t)) => Some(Stmt::Goto(t)),
+        Some(e) => Some(Stmt::ComputedGoto(e)),
+        // the ip wraps around, left for the reader to work out
+        None => Some(Stmt::ComputedGoto(Expr::Bin(
+            Box::new(expr),
+            BinOp::Add,
+            Box::new(Expr::Const(1)),
+        ))),
+    }
+}
+
+// the instructions the control-flow graph can enter other than from the one before them;
+// a computed jump can land on any instruction after it, or anywhere when it isn't relative
+fn landing_sites(program: &[OpCode], ip_reg: u8) -> HashSet<i32> {
+    let cfg = Cfg::build(program, ip_reg);
+    let mut res = HashSet::new();
+    for b in &cfg.blocks {
+        for e in &b.edges {
+            if let (Successor::Block(t), EdgeKind::Jump | EdgeKind::Taken | EdgeKind::Computed) =
+                (e.to, e.kind)
+            {
+                res.insert(cfg.blocks[t].start);
+            }
+        }
+        if b.unresolved {
+            let from = if b.edges.is_empty() { 0 } else { b.end + 1 };
+            res.extend(from..program.len() as i32);
+        }
+    }
+    res
+}
+
+// whether the value of `r` can't be read anymore after jumping to any of `from`
+fn dead(stmts: &[Option<Stmt>], r: i32, from: &[i32]) -> bool {
+    let mut visited = HashSet::new();
+    let mut queue = from.to_vec();
+
+    while let Some(k) = queue.pop() {
+        if k < 0 || k as usize >= stmts.len() || !visited.insert(k) {
+            continue;
+        }
+        match &stmts[k as usize] {
+            None => queue.push(k + 1),
+            Some(Stmt::Assign(d, e)) => {
+                if e.reads(r) {
+                    return false;
+                }
+                if *d != r {
+                    queue.push(k + 1);
+                }
+            }
+            Some(Stmt::Goto(t)) => queue.push(*t),
+            Some(Stmt::If(c, t)) => {
+                if c.reads(r) {
+                    return false;
+                }
+                queue.push(k + 1);
+                queue.push(*t);
+            }
+            Some(Stmt::ComputedGoto(_)) | Some(Stmt::Call(_)) => return false,
+        }
+    }
+    true
+}
+
+// lifts every instruction to a statement, then folds the idioms produced by jumping through
+// the ip register: `t = a > b; ip += t; ip = X` becomes `if (a <= b) goto X`
+pub fn lift(program: &[OpCode], ip_reg: u8) -> Vec<Option<Stmt>> {
+    let ip = ip_reg as i32;
+    let mut stmts: Vec<Option<Stmt>> = program
+        .iter()
+        .enumerate()
+        .map(|(i, op)| lift_op(op, i as i32, ip))
+        .collect();
+
+    // t = e; t = f(t) => t = f(e)
+    let jumped_to = landing_sites(program, ip_reg);
+    for i in 1..stmts.len() {
+        if jumped_to.contains(&(i as i32)) {
+            continue;
+        }
+        if let (Some(Stmt::Assign(r, e)), Some(Stmt::Assign(d, f))) = (&stmts[i - 1], &stmts[i]) {
+            if r == d {
+                stmts[i] = Some(Stmt::Assign(*d, f.substitute(*r, e)));
+                stmts[i - 1] = None;
+            }
+        }
+    }
+
+    // t = cond; ip += t => if (cond) skip the next instruction
+    let mut conditionals = Vec::new();
+    for i in 1..stmts.len() {
+        let t = match program[i] {
+            OpCode::AddR(a, b, c) if c == ip && a == ip && b != ip => b,
+            OpCode::AddR(a, b, c) if c == ip && b == ip && a != ip => a,
+            _ => continue,
+        };
+        if jumped_to.contains(&(i as i32)) {
+            continue;
+        }
+        if let Some(Stmt::Assign(r, e)) = &stmts[i - 1] {
+            if *r == t && e.is_condition() {
+                conditionals.push((i, t, e.clone()));
+                stmts[i] = Some(Stmt::If(Expr::Reg(t), i as i32 + 2));
+            }
+        }
+    }
+
+    // the flag can be dropped when nothing reads it afterwards
+    for (i, t, cond) in conditionals {
+        let skip = i as i32 + 2;
+        if !jumped_to.contains(&(i as i32)) && dead(&stmts, t, &[i as i32 + 1, skip]) {
+            stmts[i] = Some(Stmt::If(cond, skip));
+            stmts[i - 1] = None;
+        }
+    }
+
+    // if (cond) skip; goto X => if (!cond) goto X
+    for i in 0..stmts.len().saturating_sub(1) {
+        if jumped_to.contains(&(i as i32 + 1)) {
+            continue;
+        }
+        if let (Some(Stmt::If(c, t)), Some(Stmt::Goto(x))) = (&stmts[i], &stmts[i + 1]) {
+            if *t == i as i32 + 2 {
+                stmts[i] = Some(Stmt::If(c.clone().negate(), *x));
+                stmts[i + 1] = None;
+            }
+        }
+    }
+
+    stmts
+}
+
+// loops are closed by a backward jump at `end`, conditionals skip their body when
+// their condition holds
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Region {
+    Loop { start: i32, end: i32 },
+    Cond { head: i32, end: i32 },
+}
+
+impl Region {
+    fn span(&self) -> (i32, i32) {
+        match *self {
+            Region::Loop { start, end } => (start, end),
+            Region::Cond { head, end } => (head, end),
+        }
+    }
+
+    fn body(&self) -> (i32, i32) {
+        match *self {
+            Region::Loop { start, end } => (start, end - 1),
+            Region::Cond { head, end } => (head + 1, end),
+        }
+    }
+
+    fn jump(&self) -> i32 {
+        match *self {
+            Region::Loop { end, .. } => end,
+            Region::Cond { head, .. } => head,
+        }
+    }
+}
+
+fn regions(stmts: &[Option<Stmt>]) -> Vec<Region> {
+    let len = stmts.len() as i32;
+    let mut candidates: Vec<Region> = stmts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| {
+            let i = i as i32;
+            match s {
+                Some(Stmt::Goto(t)) | Some(Stmt::If(_, t)) if *t >= 0 && *t <= i => {
+                    Some(Region::Loop { start: *t, end: i })
+                }
+                Some(Stmt::If(_, t)) if *t > i + 1 && *t <= len => Some(Region::Cond {
+                    head: i,
+                    end: t - 1,
+                }),
+                _ => None,
+            }
+        })
+        .collect();
+    candidates.sort_by_key(|r| (r.span().0, -r.span().1));
+
+    let inside = |(lo, hi): (i32, i32), (blo, bhi): (i32, i32)| blo <= lo && hi <= bhi;
+    let mut accepted: Vec<Region> = Vec::new();
+    for c in candidates {
+        let nests = accepted.iter().all(|a| {
+            let (a_span, c_span) = (a.span(), c.span());
+            a_span.1 < c_span.0
+                || c_span.1 < a_span.0
+                || inside(c_span, a.body())
+                || inside(a_span, c.body())
+        });
+        if nests {
+            accepted.push(c);
+        }
+    }
+    accepted
+}
+
+struct Emitter<'a> {
+    stmts: &'a [Option<Stmt>],
+    regions: Vec<Region>,
+    labels: HashSet<i32>,
+    printed: HashSet<i32>,
+    out: String,
+}
+
+impl<'a> Emitter<'a> {
+    fn line(&mut self, depth: usize, text: &str) {
+        self.out.push_str(&"    ".repeat(depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn label(&mut self, i: i32) {
+        if self.labels.contains(&i) && self.printed.insert(i) {
+            self.out.push_str(&format!("L{}:\n", i));
+        }
+    }
+
+    fn target(&self, t: i32) -> String {
+        if t < 0 || t as usize >= self.stmts.len() {
+            String::from("halt")
+        } else {
+            format!("goto L{}", t)
+        }
+    }
+
+    fn stmt(&mut self, i: i32, depth: usize) {
+        let text = match &self.stmts[i as usize] {
+            None => return,
+            // all the arithmetic operators are commutative
+            Some(Stmt::Assign(r, Expr::Bin(a, op, b)))
+                if matches!(op, BinOp::Add | BinOp::Mul | BinOp::And | BinOp::Or)
+                    && (**a == Expr::Reg(*r) || **b == Expr::Reg(*r)) =>
+            {
+                let other = if **a == Expr::Reg(*r) { b } else { a };
+                format!("r{} {}= {};", r, op.symbol(), other)
+            }
+            Some(Stmt::Assign(r, e)) => format!("r{} = {};", r, e),
+            Some(Stmt::Goto(t)) => format!("{};", self.target(*t)),
+            Some(Stmt::If(c, t)) => format!("if ({}) {};", c, self.target(*t)),
+            Some(Stmt::ComputedGoto(e)) => format!("goto L[{}];", e),
+            Some(Stmt::Call(op)) => {
+                let (a, b, c) = op.operands().unwrap();
+                format!("{}({}, {}, {});", op.mnemonic(), a, b, c)
+            }
+        };
+        self.line(depth, &text);
+    }
+
+    fn emit(&mut self, lo: i32, hi: i32, depth: usize) {
+        let mut i = lo;
+        while i <= hi {
+            let region = self
+                .regions
+                .iter()
+                .filter(|r| r.span().0 == i && r.span().1 <= hi)
+                .max_by_key(|r| r.span().1)
+                .copied();
+
+            match region {
+                Some(Region::Loop { start, end }) => {
+                    let cond = match &self.stmts[end as usize] {
+                        Some(Stmt::If(c, _)) => Some(c.clone()),
+                        _ => None,
+                    };
+                    self.label(start);
+                    match &cond {
+                        Some(_) => self.line(depth, "do {"),
+                        None => self.line(depth, "while (1) {"),
+                    }
+                    self.emit(start, end - 1, depth + 1);
+                    self.label(end);
+                    match cond {
+                        Some(c) => self.line(depth, &format!("}} while ({});", c)),
+                        None => self.line(depth, "}"),
+                    }
+                    i = end + 1;
+                }
+                Some(Region::Cond { head, end }) => {
+                    self.label(head);
+                    let cond = match &self.stmts[head as usize] {
+                        Some(Stmt::If(c, _)) => c.clone().negate(),
+                        _ => unreachable!(),
+                    };
+                    self.line(depth, &format!("if ({}) {{", cond));
+                    self.emit(head + 1, end, depth + 1);
+                    self.line(depth, "}");
+                    i = end + 1;
+                }
+                None => {
+                    self.label(i);
+                    self.stmt(i, depth);
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+pub fn decompile(program: &[OpCode], ip_reg: u8) -> String {
+    let stmts = lift(program, ip_reg);
+    let regions = regions(&stmts);
+
+    // computed jumps usually skip a few instructions depending on a flag, so their base
+    // target and the following one get a label too
+    let structured: HashSet<i32> = regions.iter().map(Region::jump).collect();
+    let labels = stmts
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !structured.contains(&(*i as i32)))
+        .flat_map(|(_, s)| match s {
+            Some(Stmt::ComputedGoto(Expr::Bin(_, BinOp::Add, k))) => match **k {
+                Expr::Const(k) => vec![Some(k), k.checked_add(1)],
+                _ => vec![],
+            },
+            Some(s) => vec![s.target()],
+            None => vec![],
+        })
+        .flatten()
+        .collect();
+
+    let mut emitter = Emitter {
+        stmts: &stmts,
+        regions,
+        labels,
+        printed: HashSet::new(),
+        out: format!("// ip is bound to r{}\n", ip_reg),
+    };
+    emitter.emit(0, stmts.len() as i32 - 1, 0);
+    emitter.out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn machine(input: &str) -> Machine<6> {
+        let input: Vec<&str> = input.lines().collect();
+        Machine::parse(&input).unwrap()
+    }
+
+    const DIVISORS: &str = "#ip 5
+seti 1 5 3
+seti 1 4 2
+mulr 3 2 4
+eqrr 4 1 4
+addr 4 5 5
+addi 5 1 5
+addr 3 0 0
+addi 2 1 2
+gtrr 2 1 4
+addr 5 4 5
+seti 1 2 5
+addi 3 1 3
+gtrr 3 1 4
+addr 4 5 5
+seti 0 0 5
+mulr 5 5 5";
+
+    #[test]
+    fn test_round_trip() {
+        let m = machine(DIVISORS);
+        let text = m.to_string();
+        assert!(text.starts_with("#ip 5\nseti 1 5 3\n"));
+        let parsed = machine(&text);
+        assert_eq!(m.program(), parsed.program());
+        assert_eq!(m.ip_reg, parsed.ip_reg);
+    }
+
+    #[test]
+    fn test_lift() {
+        let m = machine(DIVISORS);
+        let stmts = lift(m.program(), m.ip_reg);
+        assert_eq!(None, stmts[2]);
+        assert_eq!(
+            Some(Stmt::If(
+                Expr::Bin(
+                    Box::new(Expr::Bin(
+                        Box::new(Expr::Reg(3)),
+                        BinOp::Mul,
+                        Box::new(Expr::Reg(2))
+                    )),
+                    BinOp::Ne,
+                    Box::new(Expr::Reg(1))
+                ),
+                7
+            )),
+            stmts[4]
+        );
+        assert_eq!(Some(Stmt::Goto(226)), stmts[15]);
+    }
+
+    #[test]
+    fn test_decompile() {
+        let m = machine(DIVISORS);
+        let expected = "// ip is bound to r5
+r3 = 1;
+do {
+    r2 = 1;
+    do {
+        if ((r3 * r2) == r1) {
+            r0 += r3;
+        }
+        r2 += 1;
+    } while (r2 <= r1);
+    r3 += 1;
+} while (r3 <= r1);
+halt;
+";
+        assert_eq!(expected, decompile(m.program(), m.ip_reg));
+    }
+
+    #[test]
+    fn test_computed_jump() {
+        let m = machine("#ip 1\naddr 1 0 1\nseti 0 0 1\naddi 0 1 0");
+        assert_eq!(
+            "// ip is bound to r1\ngoto L[r0 + 1];\nL1:\nwhile (1) {\n}\nL2:\nr0 += 1;\n",
+            decompile(m.program(), m.ip_reg)
+        );
+
+        // entering at L2 runs the increment, so it can't be folded into L1
+        let m = machine("#ip 5\naddr 4 5 5\nseti 3 0 2\naddi 2 1 2\nseti 99 0 5");
+        assert_eq!(
+            "// ip is bound to r5\ngoto L[r4 + 1];\nL1:\nr2 = 3;\nL2:\nr2 += 1;\nhalt;\n",
+            decompile(m.program(), m.ip_reg)
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let m = machine("#ip 0\naddi 0 2147483647 0");
+        assert_eq!(
+            "// ip is bound to r0\ngoto L[2147483647 + 1];\n",
+            decompile(m.program(), m.ip_reg)
+        );
+        let m = machine("#ip 1\naddi 1 2147483646 1\naddi 0 2147483646 1");
+        assert_eq!(
+            "// ip is bound to r1\nhalt;\ngoto L[r0 + 2147483647];\n",
+            decompile(m.program(), m.ip_reg)
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::BTreeMap;
+
+use crate::machine::*;
+
+// one of each instruction, with zero operands
+pub const OPCODES: [OpCode; 16] = [
+    OpCode::AddR(0, 0, 0),
+    OpCode::AddI(0, 0, 0),
+    OpCode::MulR(0, 0, 0),
+    OpCode::MulI(0, 0, 0),
+    OpCode::BanR(0, 0, 0),
+    OpCode::BanI(0, 0, 0),
+    OpCode::BorR(0, 0, 0),
+    OpCode::BorI(0, 0, 0),
+    OpCode::SetR(0, 0, 0),
+    OpCode::SetI(0, 0, 0),
+    OpCode::GtIr(0, 0, 0),
+    OpCode::GtRi(0, 0, 0),
+    OpCode::GtRr(0, 0, 0),
+    OpCode::EqIr(0, 0, 0),
+    OpCode::EqRi(0, 0, 0),
+    OpCode::EqRr(0, 0, 0),
+];
+
+// an instruction with its opcode number, as (number, a, b, c)
+pub type Instruction = (i32, i32, i32, i32);
+
+// an opcode for every number
+pub type Mapping = BTreeMap<i32, OpCode>;
+
+pub struct Sample<const N: usize, W: Word = i32> {
+    pub before: Registers<N, W>,
+    pub instruction: Instruction,
+    pub after: Registers<N, W>,
+}
+
+impl<const N: usize, W: Word> Sample<N, W> {
+    // the opcodes, with zero operands, that turn `before` into `after`
+    pub fn candidates(&self) -> Vec<OpCode> {
+        let mask = self.mask();
+        OPCODES
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| mask & (1 << i) != 0)
+            .map(|(_, op)| *op)
+            .collect()
+    }
+
+    fn mask(&self) -> u16 {
+        let (_, a, b, c) = self.instruction;
+        OPCODES
+            .iter()
+            .enumerate()
+            .filter(|(_, op)| op.with_operands(a, b, c).apply(&self.before) == self.after)
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+}
+
+pub fn decode(mapping: &Mapping, instruction: &Instruction) -> Option<OpCode> {
+    let (o, a, b, c) = *instruction;
+    mapping.get(&o).map(|op| op.with_operands(a, b, c))
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Contradiction {
+    // the index of a sample no instruction explains
+    NoMatch(usize),
+    // the index of a sample ruling out every instruction left for its number
+    Conflict(usize, i32),
+    // a number left without any instruction once the sure ones are ruled out for it
+    Exhausted(i32),
+}
+
+// what the samples tell about each opcode number: contradictory samples are reported and
+// left out, so that a single bad one doesn't hide what the others agree on
+pub struct Discovery {
+    domains: BTreeMap<i32, u16>,
+    pub contradictions: Vec<Contradiction>,
+}
+
+pub fn discover<const N: usize, W: Word>(samples: &[Sample<N, W>]) -> Discovery {
+    let mut domains: BTreeMap<i32, u16> = BTreeMap::new();
+    let mut contradictions = Vec::new();
+
+    for (i, sample) in samples.iter().enumerate() {
+        let mask = sample.mask();
+        let code = sample.instruction.0;
+        let domain = domains.entry(code).or_insert(u16::MAX);
+        if mask == 0 {
+            contradictions.push(Contradiction::NoMatch(i));
+        } else if *domain & mask == 0 {
+            contradictions.push(Contradiction::Conflict(i, code));
+        } else {
+            *domain &= mask;
+        }
+    }
+
+    // a number that can only be one instruction rules that instruction out for the others
+    loop {
+        let mut changed = false;
+        let sure: Vec<(i32, u16)> = domains
+            .iter()
+            .filter(|(_, d)| d.count_ones() == 1)
+            .map(|(&code, &d)| (code, d))
+            .collect();
+        for (code, single) in sure {
+            for (&c, d) in domains.iter_mut().filter(|(&c, _)| c != code) {
+                if *d & single != 0 {
+                    *d &= !single;
+                    changed = true;
+                    if *d == 0 {
+                        contradictions.push(Contradiction::Exhausted(c));
+                    }
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    Discovery {
+        domains,
+        contradictions,
+    }
+}
+
+impl Discovery {
+    // the instructions still possible for a number after propagation
+    pub fn candidates(&self, code: i32) -> Vec<OpCode> {
+        let domain = self.domains.get(&code).copied().unwrap_or(0);
+        (0..OPCODES.len())
+            .filter(|i| domain & (1 << i) != 0)
+            .map(|i| OPCODES[i])
+            .collect()
+    }
+
+    // the first `limit` mappings of distinct instructions to the numbers consistent with
+    // the samples, as there can be up to 16! of them
+    pub fn mappings(&self, limit: usize) -> Vec<Mapping> {
+        self.search(limit)
+    }
+
+    pub fn unique(&self) -> Option<Mapping> {
+        let mut found = self.search(2);
+        match found.len() {
+            1 => found.pop(),
+            _ => None,
+        }
+    }
+
+    // the numbers for which the consistent mappings disagree, that is which can take more
+    // than one instruction with a mapping still possible for the others
+    pub fn ambiguous(&self) -> Vec<i32> {
+        let domains: Vec<u16> = self.domains.values().copied().collect();
+        self.domains
+            .keys()
+            .enumerate()
+            .filter(|&(k, _)| {
+                let possible = (0..OPCODES.len())
+                    .filter(|i| domains[k] & (1 << i) != 0)
+                    .filter(|i| {
+                        let mut fixed = domains.clone();
+                        fixed[k] = 1 << i;
+                        complete(&fixed)
+                    })
+                    .count();
+                possible > 1
+            })
+            .map(|(_, &code)| code)
+            .collect()
+    }
+
+    // backtracking, starting with the most constrained numbers, once a matching shows that
+    // there's something to find
+    fn search(&self, limit: usize) -> Vec<Mapping> {
+        let domains: Vec<u16> = self.domains.values().copied().collect();
+        if !complete(&domains) {
+            return Vec::new();
+        }
+        let mut codes: Vec<(i32, u16)> = self.domains.iter().map(|(&c, &d)| (c, d)).collect();
+        codes.sort_by_key(|&(_, d)| d.count_ones());
+
+        let mut res = Vec::new();
+        let mut assigned = Vec::with_capacity(codes.len());
+        assign(&codes, 0, &mut assigned, &mut res, limit);
+        res
+    }
+}
+
+fn assign(
+    codes: &[(i32, u16)],
+    used: u16,
+    assigned: &mut Vec<(i32, usize)>,
+    res: &mut Vec<Mapping>,
+    limit: usize,
+) {
+    if res.len() >= limit {
+        return;
+    }
+    let (code, domain) = match codes.get(assigned.len()) {
+        Some(&c) => c,
+        None => {
+            res.push(assigned.iter().map(|&(c, i)| (c, OPCODES[i])).collect());
+            return;
+        }
+    };
+
+    for i in 0..OPCODES.len() {
+        if domain & !used & (1 << i) != 0 {
+            assigned.push((code, i));
+            assign(codes, used | 1 << i, assigned, res, limit);
+            assigned.pop();
+        }
+    }
+}
+
+// whether every number can get a distinct instruction of its domain, growing a matching
+// with augmenting paths
+fn complete(domains: &[u16]) -> bool {
+    let mut owners = [None; 16];
+    (0..domains.len()).all(|k| augment(domains, k, &mut owners, &mut 0))
+}
+
+fn augment(domains: &[u16], k: usize, owners: &mut [Option<usize>; 16], seen: &mut u16) -> bool {
+    for i in 0..OPCODES.len() {
+        if domains[k] & !*seen & (1 << i) != 0 {
+            *seen |= 1 << i;
+            let free = match owners[i] {
+                Some(other) => augment(domains, other, owners, seen),
+                None => true,
+            };
+            if free {
+                owners[i] = Some(k);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(before: [i32; 4], instruction: Instruction, after: [i32; 4]) -> Sample<4> {
+        Sample {
+            before: Registers::new(before),
+            instruction,
+            after: Registers::new(after),
+        }
+    }
+
+    // samples of every instruction, numbered backwards, on a few register values
+    fn samples() -> Vec<Sample<4>> {
+        let mut res = Vec::new();
+        let mut seed = 7;
+        for (i, op) in OPCODES.iter().enumerate() {
+            for _ in 0..6 {
+                let mut next = || {
+                    seed = (seed * 1103 + 12345) % 65_521;
+                    seed % 4
+                };
+                let before = Registers::new([next() + 1, next() * 3, next() + 5, next()]);
+                let (a, b, c) = (next(), next(), next());
+                let after = op.with_operands(a, b, c).apply(&before);
+                res.push(Sample {
+                    before,
+                    instruction: (15 - i as i32, a, b, c),
+                    after,
+                });
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn test_candidates() {
+        let s = sample([3, 2, 1, 1], (9, 2, 1, 2), [3, 2, 2, 1]);
+        assert_eq!(
+            vec![
+                OpCode::AddI(0, 0, 0),
+                OpCode::MulR(0, 0, 0),
+                OpCode::SetI(0, 0, 0)
+            ],
+            s.candidates()
+        );
+    }
+
+    #[test]
+    fn test_unique() {
+        let discovery = discover(&samples());
+        assert!(discovery.contradictions.is_empty());
+        let mapping = discovery.unique().unwrap();
+        for (i, op) in OPCODES.iter().enumerate() {
+            assert_eq!(op, &mapping[&(15 - i as i32)]);
+        }
+        assert_eq!(Some(OpCode::EqRr(1, 2, 3)), decode(&mapping, &(0, 1, 2, 3)));
+        assert!(discovery.ambiguous().is_empty());
+    }
+
+    #[test]
+    fn test_contradictions() {
+        let mut samples = samples();
+        samples.push(sample([1, 1, 1, 1], (3, 0, 0, 0), [5, 5, 5, 5]));
+        samples.push(sample([2, 0, 0, 0], (15, 0, 2, 1), [2, 4, 0, 0]));
+
+        let discovery = discover(&samples);
+        assert_eq!(
+            vec![Contradiction::NoMatch(96), Contradiction::Conflict(97, 15)],
+            discovery.contradictions
+        );
+        assert_eq!(1, discovery.mappings(10).len());
+    }
+
+    #[test]
+    fn test_exhausted() {
+        // 20 is sampled exactly like 15, so both are sure to be the same instruction
+        let mut samples = samples();
+        let copies: Vec<Sample<4>> = samples
+            .iter()
+            .filter(|s| s.instruction.0 == 15)
+            .map(|s| {
+                let (_, a, b, c) = s.instruction;
+                Sample {
+                    before: s.before.clone(),
+                    instruction: (20, a, b, c),
+                    after: s.after.clone(),
+                }
+            })
+            .collect();
+        samples.extend(copies);
+
+        let discovery = discover(&samples);
+        assert_eq!(
+            vec![Contradiction::Exhausted(20), Contradiction::Exhausted(15)],
+            discovery.contradictions
+        );
+        assert!(discovery.mappings(10).is_empty());
+        assert_eq!(None, discovery.unique());
+        assert!(discovery.ambiguous().is_empty());
+    }
+
+    #[test]
+    fn test_many() {
+        // the eight numbers can be any of the instructions leaving zeros unchanged
+        let samples: Vec<Sample<4>> = (0..8)
+            .map(|code| sample([0, 0, 0, 0], (code, 0, 0, 0), [0, 0, 0, 0]))
+            .collect();
+        let discovery = discover(&samples);
+        assert_eq!(1000, discovery.mappings(1000).len());
+        assert_eq!(None, discovery.unique());
+        assert_eq!(8, discovery.ambiguous().len());
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let samples = [
+            sample([2, 0, 0, 0], (5, 0, 2, 1), [2, 4, 0, 0]),
+            sample([2, 0, 0, 0], (6, 0, 2, 1), [2, 4, 0, 0]),
+        ];
+        let discovery = discover(&samples);
+        assert_eq!(
+            vec![OpCode::AddI(0, 0, 0), OpCode::MulI(0, 0, 0)],
+            discovery.candidates(5)
+        );
+        assert_eq!(2, discovery.mappings(10).len());
+        assert_eq!(None, discovery.unique());
+        assert_eq!(vec![5, 6], discovery.ambiguous());
+    }
+}
@@ -0,0 +1,570 @@
+use crate::machine::registry::{Builtin, Instruction, Registry};
+use crate::machine::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Idiom {
+    // for b in b..=n { if a * b == n { acc += a } }
+    DivisorLoop {
+        a: i32,
+        b: i32,
+        n: i32,
+        acc: i32,
+        tmp: i32,
+        exit: i32,
+    },
+    // for i in i..=n { acc += x }
+    MultiplyLoop {
+        acc: i32,
+        x: i32,
+        i: i32,
+        n: i32,
+        tmp: i32,
+        exit: i32,
+    },
+    // while (q + 1) * d <= n { q += 1 }
+    DivideLoop {
+        q: i32,
+        d: i32,
+        n: i32,
+        tmp: i32,
+        exit: i32,
+    },
+}
+
+impl Idiom {
+    fn registers(&self) -> Vec<i32> {
+        match *self {
+            Idiom::DivisorLoop {
+                a, b, n, acc, tmp, ..
+            } => vec![a, b, n, acc, tmp],
+            Idiom::MultiplyLoop {
+                acc, x, i, n, tmp, ..
+            } => vec![acc, x, i, n, tmp],
+            Idiom::DivideLoop { q, n, tmp, .. } => vec![q, n, tmp],
+        }
+    }
+
+    // applies the whole loop at once, returning how many instructions the interpreter would
+    // have executed, or None when the registers are outside the range where the native
+    // operation is known to match it; nothing in that range overflows, so the result doesn't
+    // depend on the overflow policy
+    pub fn apply<const N: usize, W: Word>(
+        &self,
+        regs: &mut Registers<N, W>,
+        ip_reg: &i32,
+    ) -> Option<u64> {
+        let zero = W::zero();
+        // every iteration runs the whole loop but the last one, which leaves it early
+        let executed = |iterations: W, per_iteration: u64, last: u64| {
+            let iterations = iterations.to_u64();
+            (iterations.saturating_sub(1))
+                .saturating_mul(per_iteration)
+                .saturating_add(last)
+        };
+        let res = match *self {
+            Idiom::DivisorLoop {
+                a,
+                b,
+                n,
+                acc,
+                tmp,
+                exit,
+            } => {
+                let (av, bv, nv) = (regs.get(&a), regs.get(&b), regs.get(&n));
+                if av < zero || bv < zero || nv < zero {
+                    return None;
+                }
+                let last = bv.clone().max(nv.clone());
+                if av.clone().checked_mul(last.clone()).is_none()
+                    || last.clone().checked_add(W::one()).is_none()
+                {
+                    return None;
+                }
+
+                let iterations = last
+                    .clone()
+                    .checked_sub(bv.clone())?
+                    .checked_add(W::one())?;
+
+                if av != zero
+                    && nv.clone() % av.clone() == zero
+                    && (bv..=last.clone()).contains(&(nv / av.clone()))
+                {
+                    regs.set(&acc, regs.get(&acc).checked_add(av)?);
+                }
+                regs.set(&b, last.wrapping_add(W::one()));
+                regs.set(&tmp, W::one());
+                regs.set(ip_reg, W::from_i32(exit));
+                executed(iterations, 8, 7)
+            }
+            Idiom::MultiplyLoop {
+                acc,
+                x,
+                i,
+                n,
+                tmp,
+                exit,
+            } => {
+                let (iv, nv) = (regs.get(&i), regs.get(&n));
+                let last = iv.clone().max(nv);
+                last.clone().checked_add(W::one())?;
+
+                // acc moves monotonically, so if the final value fits so do all the others
+                let iterations = last.clone().checked_sub(iv)?.checked_add(W::one())?;
+                let added = regs.get(&x).checked_mul(iterations.clone())?;
+                regs.set(&acc, regs.get(&acc).checked_add(added)?);
+                regs.set(&i, last.wrapping_add(W::one()));
+                regs.set(&tmp, W::one());
+                regs.set(ip_reg, W::from_i32(exit));
+                executed(iterations, 5, 4)
+            }
+            Idiom::DivideLoop { q, d, n, tmp, exit } => {
+                let (qv, dv, nv) = (regs.get(&q), W::from_i32(d), regs.get(&n));
+                if qv < zero || dv <= zero || nv < zero {
+                    return None;
+                }
+                let res = qv.clone().max(nv / dv.clone());
+                res.clone().checked_add(W::one())?.checked_mul(dv)?;
+                let iterations = res.clone().checked_sub(qv)?.checked_add(W::one())?;
+
+                regs.set(&q, res);
+                regs.set(&tmp, W::one());
+                regs.set(ip_reg, W::from_i32(exit));
+                executed(iterations, 7, 5)
+            }
+        };
+        Some(res)
+    }
+}
+
+// finds, for every instruction, the idiom whose loop starts there if any; idioms are loops
+// of 2018 instructions, so custom instructions are never part of one, and they run natively
+// without rewriting the program unless `lower` replaces them
+pub fn detect(program: &[OpCode], ip_reg: u8, registers: usize) -> Vec<Option<Idiom>> {
+    let ip = ip_reg as i32;
+    (0..program.len())
+        .map(|start| {
+            let code = &program[start..];
+            divisor_loop(code, start as i32, ip)
+                .or_else(|| multiply_loop(code, start as i32, ip))
+                .or_else(|| divide_loop(code, start as i32, ip))
+                .filter(|idiom| idiom.registers().iter().all(|&r| (r as usize) < registers))
+        })
+        .collect()
+}
+
+// rewrites the division loops with the `divi` of the registry, when it has one, leaving the
+// rest of the program alone. Like `Idiom::apply`, the result matches the loop only with
+// non-negative registers and no overflow, and takes fewer steps. The loops keep their length
+// and their last instructions, so jumps to the start or past the end still land where they
+// did
+pub fn lower<W: Word>(
+    program: &[OpCode],
+    ip_reg: u8,
+    registers: usize,
+    registry: &Registry<W>,
+) -> Vec<OpCode> {
+    let mut res = program.to_vec();
+    let divi = Instruction::<W>::ext(&Builtin::DivI);
+    if registry.get(&divi).is_none() {
+        return res;
+    }
+    let ip = ip_reg as i32;
+    for (start, idiom) in detect(program, ip_reg, registers).into_iter().enumerate() {
+        if let Some(Idiom::DivideLoop { q, d, n, tmp, .. }) = idiom {
+            if d <= 0 {
+                continue;
+            }
+            // q = max(q, n / d), tmp = 1, then the jump to the exit of the loop
+            let lowered = [
+                OpCode::Ext(divi, n, d, tmp),
+                OpCode::GtRr(q, tmp, tmp),
+                OpCode::AddR(tmp, ip, ip),
+                OpCode::Ext(divi, n, d, q),
+                OpCode::SetI(1, 0, tmp),
+            ];
+            res[start..start + lowered.len()].copy_from_slice(&lowered);
+        }
+    }
+    res
+}
+
+fn distinct(regs: &[i32], ip: i32) -> bool {
+    regs.iter()
+        .enumerate()
+        .all(|(k, r)| *r != ip && *r >= 0 && !regs[k + 1..].contains(r))
+}
+
+// `ip += tmp`, in either operand order
+fn skip_if(op: &OpCode, tmp: i32, ip: i32) -> bool {
+    match *op {
+        OpCode::AddR(x, y, c) => c == ip && ((x == tmp && y == ip) || (x == ip && y == tmp)),
+        _ => false,
+    }
+}
+
+// `acc += x`, in either operand order
+fn accumulate(op: &OpCode) -> Option<(i32, i32)> {
+    match *op {
+        OpCode::AddR(x, y, c) if x == c => Some((c, y)),
+        OpCode::AddR(x, y, c) if y == c => Some((c, x)),
+        _ => None,
+    }
+}
+
+fn divisor_loop(code: &[OpCode], start: i32, ip: i32) -> Option<Idiom> {
+    if code.len() < 9 {
+        return None;
+    }
+    let (a, b, tmp) = match code[0] {
+        OpCode::MulR(a, b, tmp) => (a, b, tmp),
+        _ => return None,
+    };
+    let n = match code[1] {
+        OpCode::EqRr(x, n, c) if x == tmp && c == tmp => n,
+        _ => return None,
+    };
+    if !skip_if(&code[2], tmp, ip) || code[3] != OpCode::AddI(ip, 1, ip) {
+        return None;
+    }
+    let acc = match accumulate(&code[4]) {
+        Some((acc, x)) if x == a => acc,
+        _ => return None,
+    };
+    if code[5] != OpCode::AddI(b, 1, b) || code[6] != OpCode::GtRr(b, n, tmp) {
+        return None;
+    }
+    if !skip_if(&code[7], tmp, ip) {
+        return None;
+    }
+    match code[8] {
+        OpCode::SetI(target, _, c) if c == ip && target == start - 1 => {}
+        _ => return None,
+    }
+    if !distinct(&[a, b, n, acc, tmp], ip) {
+        return None;
+    }
+
+    Some(Idiom::DivisorLoop {
+        a,
+        b,
+        n,
+        acc,
+        tmp,
+        exit: start + 9,
+    })
+}
+
+fn multiply_loop(code: &[OpCode], start: i32, ip: i32) -> Option<Idiom> {
+    if code.len() < 5 {
+        return None;
+    }
+    let (acc, x) = accumulate(&code[0])?;
+    let i = match code[1] {
+        OpCode::AddI(i, 1, c) if i == c => i,
+        _ => return None,
+    };
+    let (n, tmp) = match code[2] {
+        OpCode::GtRr(y, n, tmp) if y == i => (n, tmp),
+        _ => return None,
+    };
+    if !skip_if(&code[3], tmp, ip) {
+        return None;
+    }
+    match code[4] {
+        OpCode::SetI(target, _, c) if c == ip && target == start - 1 => {}
+        _ => return None,
+    }
+    if !distinct(&[acc, x, i, n, tmp], ip) {
+        return None;
+    }
+
+    Some(Idiom::MultiplyLoop {
+        acc,
+        x,
+        i,
+        n,
+        tmp,
+        exit: start + 5,
+    })
+}
+
+fn divide_loop(code: &[OpCode], start: i32, ip: i32) -> Option<Idiom> {
+    if code.len() < 8 {
+        return None;
+    }
+    let (q, tmp) = match code[0] {
+        OpCode::AddI(q, 1, tmp) => (q, tmp),
+        _ => return None,
+    };
+    let d = match code[1] {
+        OpCode::MulI(x, d, c) if x == tmp && c == tmp => d,
+        _ => return None,
+    };
+    let n = match code[2] {
+        OpCode::GtRr(x, n, c) if x == tmp && c == tmp => n,
+        _ => return None,
+    };
+    if !skip_if(&code[3], tmp, ip) || code[4] != OpCode::AddI(ip, 1, ip) {
+        return None;
+    }
+    let exit = match code[5] {
+        OpCode::SetI(target, _, c) if c == ip => target + 1,
+        _ => return None,
+    };
+    if code[6] != OpCode::AddI(q, 1, q) {
+        return None;
+    }
+    match code[7] {
+        OpCode::SetI(target, _, c) if c == ip && target == start - 1 => {}
+        _ => return None,
+    }
+    if !distinct(&[q, n, tmp], ip) {
+        return None;
+    }
+
+    Some(Idiom::DivideLoop { q, d, n, tmp, exit })
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Mismatch<const N: usize> {
+    pub initial: Registers<N>,
+    pub plain: Registers<N>,
+    pub optimized: Registers<N>,
+    // executed instructions, as (plain, optimized)
+    pub steps: (u64, u64),
+}
+
+// runs every initial register set through both the plain interpreter and the optimized
+// machine, comparing their registers and steps at the end, and skipping the ones for which
+// the plain run doesn't halt within `max_steps`
+pub fn check_equivalence<const N: usize>(
+    program: &[OpCode],
+    ip_reg: u8,
+    inputs: &[Registers<N>],
+    max_steps: usize,
+) -> Vec<Mismatch<N>> {
+    let mut mismatches = Vec::new();
+
+    for initial in inputs {
+        let mut plain = Machine::new(ip_reg, program.to_vec(), initial.clone());
+        for _ in 0..max_steps {
+            if plain.state != MachineState::Active {
+                break;
+            }
+            plain.execute_step();
+        }
+        if plain.state != MachineState::Halted {
+            continue;
+        }
+
+        let mut optimized = Machine::new(ip_reg, program.to_vec(), initial.clone());
+        optimized.optimize();
+        optimized.run();
+
+        if plain.registers != optimized.registers || plain.steps != optimized.steps {
+            mismatches.push(Mismatch {
+                initial: initial.clone(),
+                steps: (plain.steps, optimized.steps),
+                plain: plain.registers,
+                optimized: optimized.registers,
+            });
+        }
+    }
+
+    mismatches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn machine(input: &str) -> Machine<6> {
+        let input: Vec<&str> = input.split('\n').collect();
+        Machine::parse(&input).unwrap()
+    }
+
+    fn inputs(regs: &[i32], values: &[i32]) -> Vec<Registers<6>> {
+        let mut res = vec![Registers::new([0; 6])];
+        for &r in regs {
+            res = res
+                .into_iter()
+                .flat_map(|base| {
+                    values.iter().map(move |&v| {
+                        let mut regs = base.clone();
+                        regs.set(&r, v);
+                        regs
+                    })
+                })
+                .collect();
+        }
+        res
+    }
+
+    const DIVISORS: &str = "#ip 5
+seti 1 5 3
+seti 1 4 2
+mulr 3 2 4
+eqrr 4 1 4
+addr 4 5 5
+addi 5 1 5
+addr 3 0 0
+addi 2 1 2
+gtrr 2 1 4
+addr 5 4 5
+seti 1 2 5
+addi 3 1 3
+gtrr 3 1 4
+addr 4 5 5
+seti 0 0 5";
+
+    const MULTIPLY: &str = "#ip 5
+addi 0 0 0
+addr 0 1 0
+addi 2 1 2
+gtrr 2 3 4
+addr 4 5 5
+seti 0 0 5";
+
+    const DIVIDE: &str = "#ip 2
+addi 1 0 1
+addi 1 1 5
+muli 5 7 5
+gtrr 5 3 5
+addr 5 2 2
+addi 2 1 2
+seti 8 3 2
+addi 1 1 1
+seti 0 0 2
+setr 1 4 0";
+
+    #[test]
+    fn test_detect() {
+        let m = machine(DIVISORS);
+        let idioms = detect(&m.program, m.ip_reg, 6);
+        assert_eq!(
+            Some(Idiom::DivisorLoop {
+                a: 3,
+                b: 2,
+                n: 1,
+                acc: 0,
+                tmp: 4,
+                exit: 11
+            }),
+            idioms[2]
+        );
+        assert_eq!(1, idioms.iter().filter(|i| i.is_some()).count());
+
+        let m = machine(MULTIPLY);
+        assert!(matches!(
+            detect(&m.program, m.ip_reg, 6)[1],
+            Some(Idiom::MultiplyLoop { acc: 0, x: 1, .. })
+        ));
+
+        let m = machine(DIVIDE);
+        assert_eq!(
+            Some(Idiom::DivideLoop {
+                q: 1,
+                d: 7,
+                n: 3,
+                tmp: 5,
+                exit: 9
+            }),
+            detect(&m.program, m.ip_reg, 6)[1]
+        );
+    }
+
+    #[test]
+    fn test_divisors() {
+        let m = machine(DIVISORS);
+        let inputs = inputs(&[1], &(0..40).collect::<Vec<_>>());
+        assert_eq!(
+            Vec::<Mismatch<6>>::new(),
+            check_equivalence(&m.program, m.ip_reg, &inputs, 1_000_000)
+        );
+    }
+
+    #[test]
+    fn test_multiply() {
+        let m = machine(MULTIPLY);
+        let inputs = inputs(&[0, 1, 2, 3], &[0, 1, 2, 5, 9]);
+        assert_eq!(
+            Vec::<Mismatch<6>>::new(),
+            check_equivalence(&m.program, m.ip_reg, &inputs, 1_000_000)
+        );
+    }
+
+    #[test]
+    fn test_divide() {
+        let m = machine(DIVIDE);
+        let inputs = inputs(&[1, 3], &(0..30).collect::<Vec<_>>());
+        assert_eq!(
+            Vec::<Mismatch<6>>::new(),
+            check_equivalence(&m.program, m.ip_reg, &inputs, 1_000_000)
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let m = machine(DIVIDE);
+        let idiom = detect(&m.program, m.ip_reg, 6)[1].unwrap();
+        let mut regs = Registers::new([0, 0, 1, 100, 0, 0]);
+        assert_eq!(Some(103), idiom.apply(&mut regs, &2));
+        assert_eq!([0, 14, 9, 100, 0, 1], regs.all());
+
+        let m = machine(MULTIPLY);
+        let idiom = detect(&m.program, m.ip_reg, 6)[1].unwrap();
+        let mut regs = Registers::new([3, 4, 0, 5, 0, 1]);
+        assert_eq!(Some(29), idiom.apply(&mut regs, &5));
+        assert_eq!([27, 4, 6, 5, 1, 6], regs.all());
+        let mut regs = Registers::new([0, 1, 0, i32::MAX, 0, 1]);
+        assert_eq!(None, idiom.apply(&mut regs, &5));
+    }
+
+    #[test]
+    fn test_lower() {
+        let input: Vec<&str> = DIVIDE.split('\n').collect();
+        let mut m = machine(DIVIDE);
+        m.lower();
+        assert_eq!(machine(DIVIDE).program(), m.program());
+
+        let lowered = |initial: &Registers<6>| {
+            let mut m = Machine::<6>::parse_extended(&input, Registry::extended()).unwrap();
+            m.registers = initial.clone();
+            m.lower();
+            m
+        };
+        let program: Vec<String> = lowered(&m.registers).program()[1..6]
+            .iter()
+            .map(OpCode::to_string)
+            .collect();
+        assert_eq!(
+            vec![
+                "divi 3 7 5",
+                "gtrr 1 5 5",
+                "addr 5 2 2",
+                "divi 3 7 1",
+                "seti 1 0 5"
+            ],
+            program
+        );
+
+        for initial in inputs(&[1, 3], &(0..30).collect::<Vec<_>>()) {
+            let mut plain = Machine::new(m.ip_reg, m.program().to_vec(), initial.clone());
+            plain.run();
+            let mut lowered = lowered(&initial);
+            lowered.run();
+            assert_eq!(plain.registers, lowered.registers, "{:?}", initial);
+            assert!(lowered.steps() <= 8, "{:?}", initial);
+        }
+    }
+
+    #[test]
+    fn test_speedup() {
+        let mut m = machine(DIVISORS);
+        m.registers.set(&1, 10_000);
+        m.optimize();
+        m.run();
+        assert_eq!(24_211, m.registers.get(&0));
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::machine::*;
+
+// how a custom instruction appears in a program: its mnemonic, and whether its a and b
+// operands name registers; c always names one, like for the 2018 instructions
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub struct Ext {
+    pub mnemonic: &'static str,
+    pub registers: (bool, bool),
+}
+
+// what the machine does after a custom instruction
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Effect {
+    Continue,
+    Halt,
+    Trap,
+}
+
+// the values a program reads with `in` and writes with `out`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Io<W> {
+    pub input: VecDeque<W>,
+    pub output: Vec<W>,
+}
+
+impl<W> Default for Io<W> {
+    fn default() -> Io<W> {
+        Io {
+            input: VecDeque::new(),
+            output: Vec::new(),
+        }
+    }
+}
+
+pub trait Instruction<W: Word> {
+    fn ext(&self) -> Ext;
+
+    // the registers have been checked against the operands of `ext`, and the ip register
+    // is moved past the instruction unless it halts or traps
+    fn execute(&self, operands: (i32, i32, i32), registers: &mut [W], io: &mut Io<W>) -> Effect;
+}
+
+// the instructions beyond the 2018 set a machine understands, by mnemonic
+pub struct Registry<W: Word> {
+    instructions: HashMap<&'static str, Rc<dyn Instruction<W>>>,
+}
+
+impl<W: Word> Clone for Registry<W> {
+    fn clone(&self) -> Self {
+        Registry {
+            instructions: self.instructions.clone(),
+        }
+    }
+}
+
+impl<W: Word> fmt::Debug for Registry<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.extensions()).finish()
+    }
+}
+
+// the 2018 set alone
+impl<W: Word> Default for Registry<W> {
+    fn default() -> Registry<W> {
+        Registry {
+            instructions: HashMap::new(),
+        }
+    }
+}
+
+impl<W: Word> Registry<W> {
+    // with division, I/O and halting, see `Builtin`
+    pub fn extended() -> Registry<W> {
+        let mut registry = Registry::default();
+        for &builtin in BUILTINS.iter() {
+            registry.register(builtin);
+        }
+        registry
+    }
+
+    // replaces any instruction with the same mnemonic; the 2018 mnemonics can't be
+    // overridden, they always parse as themselves
+    pub fn register<I: Instruction<W> + 'static>(&mut self, instruction: I) {
+        let mnemonic = instruction.ext().mnemonic;
+        self.instructions.insert(mnemonic, Rc::new(instruction));
+    }
+
+    pub fn get(&self, ext: &Ext) -> Option<&dyn Instruction<W>> {
+        self.instructions
+            .get(ext.mnemonic)
+            .filter(|i| i.ext() == *ext)
+            .map(|i| i.as_ref())
+    }
+
+    pub fn extensions(&self) -> Vec<Ext> {
+        let mut res: Vec<Ext> = self.instructions.values().map(|i| i.ext()).collect();
+        res.sort_unstable_by_key(|e| e.mnemonic);
+        res
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Builtin {
+    // c = a / b and c = a % b, trapping on division by zero
+    DivR,
+    DivI,
+    ModR,
+    ModI,
+    // appends register a to the output
+    Out,
+    // moves the first input value to register c, trapping when there is none
+    In,
+    Halt,
+}
+
+pub const BUILTINS: [Builtin; 7] = [
+    Builtin::DivR,
+    Builtin::DivI,
+    Builtin::ModR,
+    Builtin::ModI,
+    Builtin::Out,
+    Builtin::In,
+    Builtin::Halt,
+];
+
+impl<W: Word> Instruction<W> for Builtin {
+    fn ext(&self) -> Ext {
+        let (mnemonic, registers) = match self {
+            Builtin::DivR => ("divr", (true, true)),
+            Builtin::DivI => ("divi", (true, false)),
+            Builtin::ModR => ("modr", (true, true)),
+            Builtin::ModI => ("modi", (true, false)),
+            Builtin::Out => ("out", (true, false)),
+            Builtin::In => ("in", (false, false)),
+            Builtin::Halt => ("halt", (false, false)),
+        };
+        Ext {
+            mnemonic,
+            registers,
+        }
+    }
+
+    fn execute(&self, (a, b, c): (i32, i32, i32), registers: &mut [W], io: &mut Io<W>) -> Effect {
+        let reg = |r: i32| registers[r as usize].clone();
+        let res = match self {
+            Builtin::DivR => reg(a).checked_div(reg(b)),
+            Builtin::DivI => reg(a).checked_div(W::from_i32(b)),
+            Builtin::ModR => reg(a).checked_rem(reg(b)),
+            Builtin::ModI => reg(a).checked_rem(W::from_i32(b)),
+            Builtin::Out => {
+                io.output.push(reg(a));
+                return Effect::Continue;
+            }
+            Builtin::In => io.input.pop_front(),
+            Builtin::Halt => return Effect::Halt,
+        };
+        match res {
+            Some(v) => {
+                registers[c as usize] = v;
+                Effect::Continue
+            }
+            None => Effect::Trap,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // outputs the digits of the input, least significant first
+    const DIGITS: [&str; 9] = [
+        "#ip 5",
+        "in 0 0 1",
+        "modi 1 10 2",
+        "divi 1 10 1",
+        "out 2 0 0",
+        "eqri 1 0 2",
+        "addr 2 5 5",
+        "seti 0 0 5",
+        "halt 0 0 0",
+    ];
+
+    #[test]
+    fn test_builtins() {
+        for engine in [Engine::Interpreter, Engine::Compiled] {
+            let mut machine = Machine::<6>::parse_extended(&DIGITS, Registry::extended()).unwrap();
+            machine.set_engine(engine);
+            machine.io.input.push_back(1984);
+            machine.run();
+            assert_eq!(MachineState::Halted, machine.state);
+            assert_eq!(vec![4, 8, 9, 1], machine.io.output);
+            assert_eq!(7, machine.curr_ip());
+            assert_eq!("in 0 0 1", machine.program()[0].to_string());
+
+            machine.state = MachineState::Active;
+            machine.registers.set(&5, 0);
+            machine.run();
+            assert_eq!(MachineState::Faulted(Fault::Trap(0)), machine.state);
+        }
+    }
+
+    #[test]
+    fn test_decompile() {
+        let machine = Machine::<6>::parse_extended(&DIGITS, Registry::extended()).unwrap();
+        let code = decompiler::decompile(machine.program(), 5);
+        let expected = "// ip is bound to r5
+in(0, 0, 1);
+do {
+    modi(1, 10, 2);
+    divi(1, 10, 1);
+    out(2, 0, 0);
+    r2 = r1 == 0;
+} while (!r2);
+halt(0, 0, 0);
+";
+        assert_eq!(expected, code);
+    }
+
+    #[test]
+    fn test_division() {
+        let input = ["#ip 3", "seti 7 0 0", "divr 0 1 2"];
+        let mut machine = Machine::<4, i64>::parse_extended(&input, Registry::extended()).unwrap();
+        machine.run();
+        assert_eq!(MachineState::Faulted(Fault::Trap(1)), machine.state);
+
+        let input = ["#ip 3", "modr 0 1 2", "divr 0 1 2"];
+        let mut machine = Machine::<4>::parse_extended(&input, Registry::extended()).unwrap();
+        machine.registers = Registers::new([-7, 2, 0, 0]);
+        machine.step(1);
+        assert_eq!(-1, machine.registers.get(&2));
+        machine.registers = Registers::new([i32::MIN, -1, 0, 1]);
+        machine.run();
+        assert_eq!(MachineState::Faulted(Fault::Trap(1)), machine.state);
+    }
+
+    struct Square;
+
+    impl Instruction<i32> for Square {
+        fn ext(&self) -> Ext {
+            Ext {
+                mnemonic: "sqr",
+                registers: (true, false),
+            }
+        }
+
+        fn execute(&self, (a, _, c): (i32, i32, i32), regs: &mut [i32], _: &mut Io<i32>) -> Effect {
+            regs[c as usize] = regs[a as usize] * regs[a as usize];
+            Effect::Continue
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = Registry::default();
+        registry.register(Square);
+        let input = ["#ip 3", "seti 12 0 0", "sqr 0 0 1", "out 1 0 0"];
+        let errors = Machine::<4>::parse_extended(&input, registry.clone()).err();
+        assert_eq!(
+            "line 4, column 1: expected an instruction, found \"out\"",
+            errors.unwrap().to_string()
+        );
+
+        let mut machine = Machine::<4>::parse_extended(&input[..3], registry).unwrap();
+        machine.run();
+        assert_eq!(144, machine.registers.get(&1));
+
+        let errors = Machine::<4>::parse_extended(&["sqr 0 0 4"], Registry::extended()).err();
+        assert_eq!(
+            "line 1, column 1: expected an instruction, found \"sqr\"",
+            errors.unwrap().to_string()
+        );
+        let mut machine = Machine::<4>::parse(&input).unwrap();
+        machine.run();
+        assert_eq!(
+            MachineState::Faulted(Fault::UnknownInstruction(1)),
+            machine.state
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use std::convert::TryInto;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::machine::registry::{Io, Registry};
+use crate::machine::*;
+
+// everything needed to resume a run; breakpoints, tracing, profiling, the engine and the
+// registry are settings of the machine rather than part of its state, and are left out
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Snapshot<const N: usize, W: Word = i32> {
+    pub ip_reg: u8,
+    pub registers: Registers<N, W>,
+    pub program: Vec<OpCode>,
+    pub steps: u64,
+    pub state: MachineState,
+    pub overflow: Overflow,
+    pub io: Io<W>,
+}
+
+// the text format is line based, with the headers in a fixed order, so that two snapshots
+// can be compared with diff:
+//
+//   #snapshot 1
+//   #ip 5
+//   #registers 0 1 0 0 0 7
+//   #steps 42
+//   #state active
+//   #overflow wrap
+//   #input 4 2
+//   #output
+//   seti 5 0 1
+//   ...
+const VERSION: &str = "1";
+const HEADERS: [&str; 8] = [
+    "snapshot",
+    "ip",
+    "registers",
+    "steps",
+    "state",
+    "overflow",
+    "input",
+    "output",
+];
+
+lazy_static! {
+    static ref HEADER_RE: Regex = Regex::new(r"^#([a-z]+)(?: (.*))?$").unwrap();
+    static ref FAULT_RE: Regex = Regex::new(r"^faulted ([a-z-]+) (-?\d+)(?: (-?\d+))?$").unwrap();
+}
+
+fn state_text(state: &MachineState) -> String {
+    match state {
+        MachineState::Active => String::from("active"),
+        MachineState::Halted => String::from("halted"),
+        MachineState::Faulted(Fault::UnknownInstruction(ip)) => {
+            format!("faulted unknown-instruction {}", ip)
+        }
+        MachineState::Faulted(Fault::InvalidRegister(ip, r)) => {
+            format!("faulted invalid-register {} {}", ip, r)
+        }
+        MachineState::Faulted(Fault::Overflow(ip)) => format!("faulted overflow {}", ip),
+        MachineState::Faulted(Fault::Trap(ip)) => format!("faulted trap {}", ip),
+    }
+}
+
+fn parse_state(text: &str) -> Option<MachineState> {
+    match text {
+        "active" => return Some(MachineState::Active),
+        "halted" => return Some(MachineState::Halted),
+        _ => {}
+    }
+    let cap = FAULT_RE.captures(text)?;
+    let ip = cap[2].parse().ok()?;
+    let register = cap.get(3).and_then(|r| r.as_str().parse().ok());
+    let fault = match (&cap[1], register) {
+        ("unknown-instruction", None) => Fault::UnknownInstruction(ip),
+        ("invalid-register", Some(r)) => Fault::InvalidRegister(ip, r),
+        ("overflow", None) => Fault::Overflow(ip),
+        ("trap", None) => Fault::Trap(ip),
+        _ => return None,
+    };
+    Some(MachineState::Faulted(fault))
+}
+
+fn overflow_text(overflow: Overflow) -> &'static str {
+    match overflow {
+        Overflow::Wrap => "wrap",
+        Overflow::Saturate => "saturate",
+        Overflow::Trap => "trap",
+    }
+}
+
+fn parse_overflow(text: &str) -> Option<Overflow> {
+    match text {
+        "wrap" => Some(Overflow::Wrap),
+        "saturate" => Some(Overflow::Saturate),
+        "trap" => Some(Overflow::Trap),
+        _ => None,
+    }
+}
+
+fn parse_words<W: Word>(text: &str) -> Option<Vec<W>> {
+    text.split_whitespace().map(|v| v.parse().ok()).collect()
+}
+
+fn words_text<W: Word>(words: &[W]) -> String {
+    let words: Vec<String> = words.iter().map(W::to_string).collect();
+    words.join(" ")
+}
+
+fn parse_registers<const N: usize, W: Word>(text: &str) -> Option<Registers<N, W>> {
+    let values: Vec<W> = parse_words(text)?;
+    values.try_into().ok().map(Registers::new)
+}
+
+impl<const N: usize, W: Word> fmt::Display for Snapshot<N, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // empty lists leave their header alone, without a trailing space
+        let header = |name: &str, value: String| match value.as_str() {
+            "" => format!("#{}", name),
+            _ => format!("#{} {}", name, value),
+        };
+        let input: Vec<W> = self.io.input.iter().cloned().collect();
+        writeln!(f, "#snapshot {}", VERSION)?;
+        writeln!(f, "#ip {}", self.ip_reg)?;
+        writeln!(f, "#registers {}", words_text(&self.registers.all()))?;
+        writeln!(f, "#steps {}", self.steps)?;
+        writeln!(f, "#state {}", state_text(&self.state))?;
+        writeln!(f, "#overflow {}", overflow_text(self.overflow))?;
+        writeln!(f, "{}", header("input", words_text(&input)))?;
+        writeln!(f, "{}", header("output", words_text(&self.io.output)))?;
+        for op in &self.program {
+            writeln!(f, "{}", op)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, W: Word> Snapshot<N, W> {
+    // a program using custom instructions must be parsed with the registry of the machine
+    // it is restored into
+    pub fn parse(input: &[&str]) -> Result<Snapshot<N, W>, ParseErrors> {
+        Snapshot::parse_extended(input, &Registry::default())
+    }
+
+    pub fn parse_extended(
+        input: &[&str],
+        registry: &Registry<W>,
+    ) -> Result<Snapshot<N, W>, ParseErrors> {
+        let mut errors = Vec::new();
+
+        // the headers, in order, then the program; values are kept with their column
+        let mut values = Vec::new();
+        for (i, (&line, &header)) in input.iter().zip(HEADERS.iter()).enumerate() {
+            let expected = format!("a #{} header", header);
+            match HEADER_RE.captures(line) {
+                Some(cap) if &cap[1] == header => values.push(match cap.get(2) {
+                    Some(m) => (m.start() + 1, m.as_str()),
+                    None => (line.len() + 1, ""),
+                }),
+                _ => {
+                    let e = ParseError::new(1, line, &expected).at_line(i + 1);
+                    return Err(ParseErrors(vec![e]));
+                }
+            }
+        }
+        if values.len() < HEADERS.len() {
+            let expected = format!("a #{} header", HEADERS[values.len()]);
+            let e = ParseError::new(1, "", &expected).at_line(input.len() + 1);
+            return Err(ParseErrors(vec![e]));
+        }
+
+        let mut field = |i: usize, expected: &str, ok: bool| {
+            if !ok {
+                let (column, text) = values[i];
+                let e = ParseError::new(column, text, expected);
+                errors.push(e.at_line(i + 1));
+            }
+        };
+        field(0, VERSION, values[0].1 == VERSION);
+        let ip_reg = values[1].1.parse().ok();
+        field(1, "an ip register", ip_reg.is_some());
+        let registers = parse_registers(values[2].1);
+        field(2, &format!("{} register values", N), registers.is_some());
+        let steps = values[3].1.parse().ok();
+        field(3, "a step count", steps.is_some());
+        let state = parse_state(values[4].1);
+        field(4, "a machine state", state.is_some());
+        let overflow = parse_overflow(values[5].1);
+        field(5, "wrap, saturate or trap", overflow.is_some());
+        let io_input = parse_words(values[6].1);
+        field(6, "input values", io_input.is_some());
+        let io_output = parse_words(values[7].1);
+        field(7, "output values", io_output.is_some());
+
+        let extensions = registry.extensions();
+        let mut program = Vec::new();
+        for (i, &line) in input.iter().enumerate().skip(HEADERS.len()) {
+            // unknown instructions are saved as nop, so any other mnemonic is one the
+            // registry should know
+            let res = match (line, OpCode::parse(line, None, &extensions)) {
+                ("nop", _) => Ok(OpCode::Nop),
+                (_, Ok(OpCode::Nop)) => {
+                    let mnemonic = line.split(' ').next().unwrap_or(line);
+                    Err(ParseError::new(1, mnemonic, "an instruction"))
+                }
+                (_, res) => res,
+            };
+            match res {
+                Ok(op) => program.push(op),
+                Err(e) => errors.push(e.at_line(i + 1)),
+            }
+        }
+
+        match (
+            ip_reg, registers, steps, state, overflow, io_input, io_output,
+        ) {
+            (
+                Some(ip_reg),
+                Some(registers),
+                Some(steps),
+                Some(state),
+                Some(overflow),
+                Some(input),
+                Some(output),
+            ) if errors.is_empty() => Ok(Snapshot {
+                ip_reg,
+                registers,
+                program,
+                steps,
+                state,
+                overflow,
+                io: Io {
+                    input: input.into_iter().collect(),
+                    output,
+                },
+            }),
+            _ => Err(ParseErrors(errors)),
+        }
+    }
+}
+
+impl<const N: usize, W: Word> Machine<N, W> {
+    pub fn snapshot(&self) -> Snapshot<N, W> {
+        Snapshot {
+            ip_reg: self.ip_reg,
+            registers: self.registers.clone(),
+            program: self.program.clone(),
+            steps: self.steps,
+            state: self.state,
+            overflow: self.overflow,
+            io: self.io.clone(),
+        }
+    }
+
+    // replaces the state of the machine, keeping its settings: an optimized or compiled
+    // machine stays so, with the restored program
+    pub fn restore(&mut self, snapshot: &Snapshot<N, W>) {
+        self.ip_reg = snapshot.ip_reg;
+        self.registers = snapshot.registers.clone();
+        self.program = snapshot.program.clone();
+        self.steps = snapshot.steps;
+        self.paused = None;
+        self.state = snapshot.state;
+        self.overflow = snapshot.overflow;
+        self.io = snapshot.io.clone();
+
+        if !self.accelerators.is_empty() {
+            self.optimize();
+        }
+        let engine = self.engine();
+        self.set_engine(engine);
+    }
+
+    pub fn from_snapshot(snapshot: &Snapshot<N, W>) -> Machine<N, W> {
+        let mut machine = Machine::new(
+            snapshot.ip_reg,
+            snapshot.program.clone(),
+            snapshot.registers.clone(),
+        );
+        machine.restore(snapshot);
+        machine
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROGRAM: [&str; 7] = [
+        "#ip 5",
+        "seti 3 0 1",
+        "mulr 1 1 1",
+        "addi 1 1 1",
+        "gtri 1 1000 2",
+        "addr 5 2 5",
+        "seti 0 0 5",
+    ];
+
+    #[test]
+    fn test_format() {
+        let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+        machine.step(3);
+        let text = machine.snapshot().to_string();
+        assert_eq!(
+            "#snapshot 1\n\
+             #ip 5\n\
+             #registers 0 10 0 0 0 3\n\
+             #steps 3\n\
+             #state active\n\
+             #overflow wrap\n\
+             #input\n\
+             #output\n\
+             seti 3 0 1\n\
+             mulr 1 1 1\n\
+             addi 1 1 1\n\
+             gtri 1 1000 2\n\
+             addr 5 2 5\n\
+             seti 0 0 5\n",
+            text
+        );
+        let parsed = Snapshot::<6>::parse(&lines(&text)).unwrap();
+        assert_eq!(machine.snapshot(), parsed);
+    }
+
+    #[test]
+    fn test_resume() {
+        let mut machine = Machine::<6>::parse(&PROGRAM).unwrap();
+        machine.run();
+
+        let mut first = Machine::<6>::parse(&PROGRAM).unwrap();
+        first.set_engine(Engine::Compiled);
+        first.step(4);
+        let text = first.snapshot().to_string();
+
+        let mut second = Machine::<6, i64>::from_snapshot(&Snapshot::parse(&lines(&text)).unwrap());
+        assert_eq!(Engine::Interpreter, second.engine());
+        second.run();
+        assert_eq!(machine.steps(), second.steps());
+        assert_eq!(
+            machine.registers.all().to_vec(),
+            second
+                .registers
+                .all()
+                .iter()
+                .map(|&r| r as i32)
+                .collect::<Vec<i32>>()
+        );
+
+        first.restore(&machine.snapshot());
+        assert_eq!(Engine::Compiled, first.engine());
+        assert_eq!(MachineState::Halted, first.state);
+    }
+
+    #[test]
+    fn test_fault() {
+        let mut machine = Machine::<3>::parse(&["#ip 0", "addr 1 7 1", "bad 1 2 3"]).unwrap();
+        machine.run();
+        let text = machine.snapshot().to_string();
+        assert!(text.contains("#state faulted invalid-register 0 7\n"));
+        let parsed = Snapshot::<3>::parse(&lines(&text)).unwrap();
+        assert_eq!(machine.snapshot(), parsed);
+        assert_eq!(OpCode::Nop, parsed.program[1]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let input = [
+            "#snapshot 1",
+            "#ip 5",
+            "#registers 0 1 2",
+            "#steps 12",
+            "#state sleeping",
+            "#overflow wrap",
+            "#input 1 x",
+            "#output",
+            "seti 1 2 3",
+            "seti x",
+        ];
+        let errors = Snapshot::<6>::parse(&input).err().unwrap().0;
+        let found: Vec<(usize, usize, &str)> = errors
+            .iter()
+            .map(|e| (e.line, e.column, e.text.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                (3, 12, "0 1 2"),
+                (5, 8, "sleeping"),
+                (7, 8, "1 x"),
+                (10, 1, "seti x")
+            ],
+            found
+        );
+
+        let errors = Snapshot::<6>::parse(&input[..4]).err().unwrap();
+        assert_eq!(
+            "line 5, column 1: expected a #state header, found \"\"",
+            errors.to_string()
+        );
+    }
+
+    #[test]
+    fn test_extended() {
+        let input = ["#ip 3", "in 0 0 0", "out 0 0 0", "addi 0 1 0", "out 0 0 0"];
+        let load = || Machine::<4>::parse_extended(&input, Registry::extended()).unwrap();
+        let mut machine = load();
+        machine.io.input.extend([7, 8]);
+        machine.step(2);
+        let text = machine.snapshot().to_string();
+        assert!(text.contains("#input 8\n#output 7\nin 0 0 0\nout 0 0 0\n"));
+
+        let errors = Snapshot::<4>::parse(&lines(&text)).err().unwrap();
+        assert_eq!(
+            "line 9, column 1: expected an instruction, found \"in\"",
+            errors.to_string().lines().next().unwrap()
+        );
+
+        let snapshot = Snapshot::parse_extended(&lines(&text), machine.registry()).unwrap();
+        assert_eq!(machine.snapshot(), snapshot);
+        let mut restored = load();
+        restored.restore(&snapshot);
+        restored.run();
+        assert_eq!(MachineState::Halted, restored.state);
+        assert_eq!(vec![7, 8], restored.io.output);
+        assert_eq!(vec![8], Vec::from(restored.io.input));
+    }
+}
@@ -0,0 +1,521 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+
+use crate::machine::decompiler::{BinOp, Expr};
+use crate::machine::*;
+
+// the values a symbol can take: an interval minus some values, with no bound on the
+// sides where the word has none
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Domain<W = i32> {
+    pub min: Option<W>,
+    pub max: Option<W>,
+    pub excluded: BTreeSet<W>,
+}
+
+impl<W: Word> Domain<W> {
+    fn full() -> Domain<W> {
+        Domain {
+            min: W::min_value(),
+            max: W::max_value(),
+            excluded: BTreeSet::new(),
+        }
+    }
+
+    fn in_bounds(&self, v: &W) -> bool {
+        self.min.as_ref().is_none_or(|min| min <= v) && self.max.as_ref().is_none_or(|max| v <= max)
+    }
+
+    pub fn contains(&self, v: W) -> bool {
+        self.in_bounds(&v) && !self.excluded.contains(&v)
+    }
+
+    pub fn value(&self) -> Option<W> {
+        match (&self.min, &self.max) {
+            (Some(min), Some(max)) if min == max => Some(min.clone()),
+            _ => None,
+        }
+    }
+
+    // the excluded values are all in the interval, so they leave nothing when they are
+    // as many as max - min + 1
+    fn is_empty(&self) -> bool {
+        let (min, max) = match (&self.min, &self.max) {
+            (Some(min), Some(max)) => (min.clone(), max.clone()),
+            _ => return false,
+        };
+        if min > max {
+            return true;
+        }
+        let excluded = W::from_i32(self.excluded.len().min(i32::MAX as usize) as i32);
+        max.checked_sub(min).is_some_and(|d| d < excluded)
+    }
+
+    // false when no value is left
+    fn constrain(&mut self, op: BinOp, k: W) -> bool {
+        let raise = |min: &mut Option<W>, k: W| {
+            *min = Some(min.take().map_or(k.clone(), |min| min.max(k)));
+        };
+        let lower = |max: &mut Option<W>, k: W| {
+            *max = Some(max.take().map_or(k.clone(), |max| max.min(k)));
+        };
+        match op {
+            BinOp::Eq => {
+                raise(&mut self.min, k.clone());
+                lower(&mut self.max, k);
+            }
+            BinOp::Ne => {
+                self.excluded.insert(k);
+            }
+            BinOp::Gt => match k.checked_add(W::one()) {
+                Some(k) => raise(&mut self.min, k),
+                None => return false,
+            },
+            BinOp::Le => lower(&mut self.max, k),
+            _ => {}
+        }
+
+        let excluded = std::mem::take(&mut self.excluded);
+        self.excluded = excluded.into_iter().filter(|v| self.in_bounds(v)).collect();
+        while let (Some(min), Some(max)) = (&self.min, &self.max) {
+            if min >= max || !self.excluded.remove(min) {
+                break;
+            }
+            self.min = Some(min.clone().wrapping_add(W::one()));
+        }
+        while let (Some(min), Some(max)) = (&self.min, &self.max) {
+            if min >= max || !self.excluded.remove(max) {
+                break;
+            }
+            self.max = Some(max.clone().wrapping_add(W::from_i32(-1)));
+        }
+        !self.is_empty()
+    }
+}
+
+// an expression split in a part without constants and a constant added to it
+fn split<W: Word>(e: &Expr<W>) -> (Option<Expr<W>>, W) {
+    match e {
+        Expr::Const(c) => (None, c.clone()),
+        Expr::Bin(x, BinOp::Add, y) => match y.as_ref() {
+            Expr::Const(c) => (Some(x.as_ref().clone()), c.clone()),
+            _ => (Some(e.clone()), W::zero()),
+        },
+        _ => (Some(e.clone()), W::zero()),
+    }
+}
+
+// folds constants with the overflow policy of the machine, None when they trap
+fn bin<W: Word>(a: Expr<W>, op: BinOp, b: Expr<W>, overflow: Overflow) -> Option<Expr<W>> {
+    match (a, b) {
+        (Expr::Const(a), Expr::Const(b)) => op.eval(a, b, overflow).map(Expr::Const),
+        (a, b) => Some(Expr::Bin(Box::new(a), op, Box::new(b))),
+    }
+}
+
+// keeps additions in the form `e + k`, so that conditions on them can be solved; the
+// constants of `e + c + k` are only merged when that doesn't overflow
+fn add<W: Word>(a: Expr<W>, b: Expr<W>, overflow: Overflow) -> Option<Expr<W>> {
+    let (e, k) = match (a, b) {
+        (Expr::Const(k), e) | (e, Expr::Const(k)) => (e, k),
+        (a, b) => return bin(a, BinOp::Add, b, overflow),
+    };
+    let res = match split(&e) {
+        (None, c) => Expr::Const(c.add_with(k, overflow)?),
+        (Some(x), c) => match c.checked_add(k.clone()) {
+            Some(k) if k == W::zero() => x,
+            Some(k) => Expr::Bin(Box::new(x), BinOp::Add, Box::new(Expr::Const(k))),
+            None => Expr::Bin(Box::new(e), BinOp::Add, Box::new(Expr::Const(k))),
+        },
+    };
+    Some(res)
+}
+
+// a condition as `r op k` for the initial value of register r, when it's that simple
+fn simple<W: Word>(cond: &Expr<W>) -> Option<(i32, BinOp, W)> {
+    let (a, op, b) = match cond {
+        Expr::Bin(a, op, b) => (a.as_ref(), *op, b.as_ref()),
+        _ => return None,
+    };
+    let (e, op, k) = match (a, op, b) {
+        (e, op, Expr::Const(k)) => (e, op, k.clone()),
+        (Expr::Const(k), BinOp::Eq, e) | (Expr::Const(k), BinOp::Ne, e) => (e, op, k.clone()),
+        // k > e is e <= k - 1, k <= e is e > k - 1
+        (Expr::Const(k), BinOp::Gt, e) => (e, BinOp::Le, k.clone().checked_sub(W::one())?),
+        (Expr::Const(k), BinOp::Le, e) => (e, BinOp::Gt, k.clone().checked_sub(W::one())?),
+        _ => return None,
+    };
+    match split(e) {
+        (Some(Expr::Reg(r)), c) => Some((r, op, k.checked_sub(c)?)),
+        _ => None,
+    }
+}
+
+// a program reaching its end: the symbols satisfy all the constraints, and the domains
+// of the ones that only appear in simple conditions
+#[derive(Clone, Debug)]
+pub struct Halt<W = i32> {
+    pub steps: u64,
+    pub constraints: Vec<Expr<W>>,
+    pub domains: BTreeMap<i32, Domain<W>>,
+}
+
+impl<W: Word> Halt<W> {
+    // the only initial value of register r leading to this halt, if there's one
+    pub fn value(&self, r: i32) -> Option<W> {
+        self.domains.get(&r).and_then(Domain::value)
+    }
+
+    // whether the domains capture all the constraints, or some of them couldn't be solved
+    pub fn is_exact(&self) -> bool {
+        self.constraints.iter().all(|c| simple(c).is_some())
+    }
+}
+
+#[derive(Debug)]
+pub struct Exploration<W = i32> {
+    // sorted by steps
+    pub halts: Vec<Halt<W>>,
+    pub faulted: usize,
+    // paths cut by the step limit, or jumping to an address depending on a symbol
+    pub unexplored: usize,
+}
+
+#[derive(Clone)]
+struct Path<W> {
+    registers: Vec<Expr<W>>,
+    constraints: Vec<Expr<W>>,
+    domains: BTreeMap<i32, Domain<W>>,
+    steps: u64,
+}
+
+enum Outcome<W> {
+    Halted,
+    Faulted,
+    Unexplored,
+    Infeasible,
+    Fork(Path<W>),
+}
+
+impl<W: Word> Path<W> {
+    // false when the path becomes impossible
+    fn assume(&mut self, cond: Expr<W>) -> bool {
+        let feasible = match simple(&cond) {
+            Some((r, op, k)) => {
+                let domain = self.domains.entry(r).or_insert_with(Domain::full);
+                domain.constrain(op, k)
+            }
+            None => true,
+        };
+        self.constraints.push(cond);
+        feasible
+    }
+
+    // runs until the path ends or forks into this one and the returned one
+    fn advance(
+        &mut self,
+        program: &[OpCode],
+        ip_reg: usize,
+        overflow: Overflow,
+        max_steps: u64,
+    ) -> Outcome<W> {
+        let n = self.registers.len();
+        if ip_reg >= n {
+            return Outcome::Faulted;
+        }
+        loop {
+            let ip = match &self.registers[ip_reg] {
+                Expr::Const(ip) => ip.clone().to_i32(),
+                _ => return Outcome::Unexplored,
+            };
+            let op = match program.get(ip as usize) {
+                Some(op) => op,
+                None => return Outcome::Halted,
+            };
+            if self.steps >= max_steps {
+                return Outcome::Unexplored;
+            }
+            let (a, b, c) = match op.operands() {
+                Some(operands) if op.invalid_register(n).is_none() => operands,
+                _ => return Outcome::Faulted,
+            };
+
+            let (reg_a, reg_b) = op.register_operands();
+            let value = |x: i32, register: bool| match register {
+                true => self.registers[x as usize].clone(),
+                false => Expr::Const(W::from_i32(x)),
+            };
+            let (x, y) = (value(a, reg_a), value(b, reg_b));
+            let mut fork = None;
+            let res = match op {
+                OpCode::AddR(..) | OpCode::AddI(..) => add(x, y, overflow),
+                OpCode::MulR(..) | OpCode::MulI(..) => bin(x, BinOp::Mul, y, overflow),
+                OpCode::BanR(..) | OpCode::BanI(..) => bin(x, BinOp::And, y, overflow),
+                OpCode::BorR(..) | OpCode::BorI(..) => bin(x, BinOp::Or, y, overflow),
+                OpCode::SetR(..) | OpCode::SetI(..) => Some(x),
+                OpCode::Ext(..) => return Outcome::Unexplored,
+                _ => {
+                    let cmp = match op {
+                        OpCode::GtIr(..) | OpCode::GtRi(..) | OpCode::GtRr(..) => BinOp::Gt,
+                        _ => BinOp::Eq,
+                    };
+                    match bin(x, cmp, y, overflow) {
+                        Some(Expr::Const(v)) => Some(Expr::Const(v)),
+                        Some(cond) => {
+                            fork = Some(cond);
+                            Some(Expr::Const(W::one()))
+                        }
+                        None => None,
+                    }
+                }
+            };
+            self.registers[c as usize] = match res {
+                Some(res) => res,
+                None => return Outcome::Faulted,
+            };
+
+            let mut other = None;
+            if let Some(cond) = fork {
+                let mut negated = self.clone();
+                negated.registers[c as usize] = Expr::Const(W::zero());
+                let feasible = (self.assume(cond.clone()), negated.assume(cond.negate()));
+                match feasible {
+                    (true, true) => other = Some(negated),
+                    (true, false) => {}
+                    (false, true) => *self = negated,
+                    (false, false) => return Outcome::Infeasible,
+                }
+            }
+
+            // the ip always wraps, whatever the overflow policy
+            for path in std::iter::once(&mut *self).chain(other.as_mut()) {
+                let ip = path.registers[ip_reg].clone();
+                path.registers[ip_reg] = match ip {
+                    Expr::Const(ip) => Expr::Const(ip.wrapping_add(W::one())),
+                    ip => add(ip, Expr::Const(W::one()), Overflow::Wrap).unwrap(),
+                };
+                path.steps += 1;
+            }
+            if let Some(other) = other {
+                return Outcome::Fork(other);
+            }
+        }
+    }
+}
+
+// explores the paths of the program, treating the initial values of `symbols` as unknown
+// and forking on every comparison involving them, shortest paths first, until `max_halts`
+// halting paths are found. Constants are folded like the machine would with `overflow`,
+// while conditions are solved over unbounded integers, ignoring overflow, and only when
+// they compare a symbol plus a constant to a constant
+pub fn explore<const N: usize, W: Word>(
+    program: &[OpCode],
+    ip_reg: u8,
+    initial: &Registers<N, W>,
+    overflow: Overflow,
+    symbols: &[i32],
+    max_steps: u64,
+    max_halts: usize,
+) -> Exploration<W> {
+    let registers = (0..N as i32)
+        .map(|r| match symbols.contains(&r) && r != ip_reg as i32 {
+            true => Expr::Reg(r),
+            false => Expr::Const(initial.get(&r)),
+        })
+        .collect();
+    let start = Path {
+        registers,
+        constraints: Vec::new(),
+        domains: BTreeMap::new(),
+        steps: 0,
+    };
+
+    let mut res = Exploration {
+        halts: Vec::new(),
+        faulted: 0,
+        unexplored: 0,
+    };
+    let mut queue = Queue::new();
+    queue.push(start, false);
+
+    // halted paths go back in the queue, so that they come out in order of steps
+    while let Some((mut path, halted)) = queue.pop() {
+        if halted {
+            res.halts.push(Halt {
+                steps: path.steps,
+                constraints: path.constraints,
+                domains: path.domains,
+            });
+            if res.halts.len() >= max_halts {
+                res.unexplored += queue.paths.len();
+                break;
+            }
+            continue;
+        }
+
+        match path.advance(program, ip_reg as usize, overflow, max_steps) {
+            Outcome::Halted => queue.push(path, true),
+            Outcome::Faulted => res.faulted += 1,
+            Outcome::Unexplored => res.unexplored += 1,
+            Outcome::Infeasible => {}
+            Outcome::Fork(other) => {
+                queue.push(path, false);
+                queue.push(other, false);
+            }
+        }
+    }
+    res
+}
+
+struct Queue<W> {
+    paths: HashMap<usize, (Path<W>, bool)>,
+    order: BinaryHeap<Reverse<(u64, usize)>>,
+    next: usize,
+}
+
+impl<W> Queue<W> {
+    fn new() -> Queue<W> {
+        Queue {
+            paths: HashMap::new(),
+            order: BinaryHeap::new(),
+            next: 0,
+        }
+    }
+
+    fn push(&mut self, path: Path<W>, halted: bool) {
+        self.order.push(Reverse((path.steps, self.next)));
+        self.paths.insert(self.next, (path, halted));
+        self.next += 1;
+    }
+
+    fn pop(&mut self) -> Option<(Path<W>, bool)> {
+        let Reverse((_, id)) = self.order.pop()?;
+        self.paths.remove(&id)
+    }
+}
+
+impl<const N: usize, W: Word> Machine<N, W> {
+    pub fn explore(&self, symbols: &[i32], max_steps: u64, max_halts: usize) -> Exploration<W> {
+        explore(
+            &self.program,
+            self.ip_reg,
+            &self.registers,
+            self.overflow,
+            symbols,
+            max_steps,
+            max_halts,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_equality() {
+        let input = [
+            "#ip 5",
+            "seti 7 0 1",
+            "eqrr 0 1 2",
+            "addr 2 5 5",
+            "seti 0 0 5",
+        ];
+        let machine = Machine::<6>::parse(&input).unwrap();
+        let res = machine.explore(&[0], 1000, 10);
+        assert_eq!(1, res.halts.len());
+        assert_eq!(3, res.halts[0].steps);
+        assert_eq!(Some(7), res.halts[0].value(0));
+        assert_eq!("r0 == 7", res.halts[0].constraints[0].to_string());
+        assert!(res.halts[0].is_exact());
+        assert_eq!(1, res.unexplored);
+    }
+
+    #[test]
+    fn test_counting() {
+        // increments r0 until it's above 10
+        let input = [
+            "#ip 5",
+            "seti 0 0 4",
+            "addi 0 1 0",
+            "gtri 0 10 2",
+            "addr 2 5 5",
+            "seti 0 0 5",
+        ];
+        let res = Machine::<6>::parse(&input).unwrap().explore(&[0], 1000, 3);
+        let halts: Vec<(u64, i32, Option<i32>)> = res
+            .halts
+            .iter()
+            .map(|h| (h.steps, h.domains[&0].min.unwrap(), h.value(0)))
+            .collect();
+        assert_eq!(
+            vec![(4, 10, None), (8, 9, Some(9)), (12, 8, Some(8))],
+            halts
+        );
+        assert_eq!(Some(i32::MAX), res.halts[0].domains[&0].max);
+    }
+
+    #[test]
+    fn test_unsolved() {
+        let input = [
+            "#ip 5",
+            "mulr 0 0 1",
+            "eqri 1 49 2",
+            "addr 2 5 5",
+            "seti 0 0 0",
+        ];
+        let res = Machine::<6>::parse(&input).unwrap().explore(&[0], 100, 10);
+        assert_eq!(2, res.halts.len());
+        assert!(!res.halts[0].is_exact());
+        assert_eq!("(r0 * r0) == 49", res.halts[0].constraints[0].to_string());
+        assert_eq!(None, res.halts[0].value(0));
+    }
+
+    #[test]
+    fn test_wide() {
+        let input = [
+            "#ip 5",
+            "seti 7 0 1",
+            "eqrr 0 1 2",
+            "addr 2 5 5",
+            "seti 0 0 5",
+        ];
+        let mut machine = Machine::<6, u64>::parse(&input).unwrap();
+        let res = machine.explore(&[0], 1000, 10);
+        assert_eq!(Some(7), res.halts[0].value(0));
+
+        // r0 is a known value, however wide, that never halts the program
+        machine.registers.set(&0, 1 << 40);
+        let res = machine.explore(&[], 1000, 10);
+        assert!(res.halts.is_empty());
+        assert_eq!(1, res.unexplored);
+    }
+
+    #[test]
+    fn test_overflow() {
+        // halts when i32::MAX + 1 is positive
+        let input = [
+            "#ip 5",
+            "seti 2147483647 0 1",
+            "addi 1 1 1",
+            "gtri 1 0 2",
+            "addr 2 5 5",
+            "seti 0 0 5",
+            "seti 99 0 5",
+        ];
+        let mut machine = Machine::<6, i64>::parse(&input).unwrap();
+        let res = machine.explore(&[], 1000, 10);
+        assert_eq!(1, res.halts.len());
+        assert_eq!(5, res.halts[0].steps);
+        assert_eq!(0, res.unexplored);
+        machine.run();
+        assert_eq!(1, machine.registers.get(&2));
+
+        let mut machine = Machine::<6>::parse(&input).unwrap();
+        assert!(machine.explore(&[], 1000, 10).halts.is_empty());
+        machine.set_overflow(Overflow::Saturate);
+        assert_eq!(1, machine.explore(&[], 1000, 10).halts.len());
+        machine.set_overflow(Overflow::Trap);
+        assert_eq!(1, machine.explore(&[], 1000, 10).faulted);
+    }
+}
@@ -0,0 +1,246 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::machine::*;
+
+pub(super) struct Tracer {
+    writer: Box<dyn Write>,
+    error: Option<io::Error>,
+}
+
+#[derive(Default)]
+pub struct Profile {
+    steps: u64,
+    hits: Vec<u64>,
+    accelerated: Vec<u64>,
+    back_jumps: HashMap<(i32, i32), u64>,
+}
+
+// a loop closed by a backward jump from `end` to `start`
+#[derive(PartialEq, Eq, Debug)]
+pub struct HotLoop {
+    pub start: i32,
+    pub end: i32,
+    pub iterations: u64,
+    pub executed: u64,
+}
+
+impl Profile {
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn hits(&self, ip: i32) -> u64 {
+        self.hits.get(ip as usize).copied().unwrap_or(0)
+    }
+
+    pub fn accelerated(&self, ip: i32) -> u64 {
+        self.accelerated.get(ip as usize).copied().unwrap_or(0)
+    }
+
+    pub fn hot_loops(&self) -> Vec<HotLoop> {
+        let mut res: Vec<HotLoop> = self
+            .back_jumps
+            .iter()
+            .map(|(&(end, start), &iterations)| HotLoop {
+                start,
+                end,
+                iterations,
+                executed: (start..=end).map(|ip| self.hits(ip)).sum(),
+            })
+            .collect();
+        res.sort_by_key(|l| (Reverse(l.executed), l.start, l.end));
+        res
+    }
+
+    pub fn report(&self, program: &[OpCode]) -> String {
+        let mut res = String::new();
+        writeln!(res, "{} steps", self.steps).unwrap();
+        writeln!(res, "  ip  instruction       hits      share").unwrap();
+        for (ip, op) in program.iter().enumerate() {
+            let ip = ip as i32;
+            let hits = self.hits(ip);
+            let share = if self.steps == 0 {
+                0.0
+            } else {
+                hits as f64 * 100.0 / self.steps as f64
+            };
+            write!(res, "{:>4}  {:<16} {:>10} {:>9.2}%", ip, op, hits, share).unwrap();
+            if self.accelerated(ip) > 0 {
+                write!(res, "  ({} accelerated)", self.accelerated(ip)).unwrap();
+            }
+            res.push('\n');
+        }
+
+        writeln!(res, "hot loops").unwrap();
+        for l in self.hot_loops() {
+            writeln!(
+                res,
+                "  {:>4}..={:<4} {:>10} iterations {:>12} instructions",
+                l.start, l.end, l.iterations, l.executed
+            )
+            .unwrap();
+        }
+        res
+    }
+
+    fn record(&mut self, ip: i32, next: i32, accelerated: bool, len: usize) {
+        if self.hits.len() < len {
+            self.hits.resize(len, 0);
+            self.accelerated.resize(len, 0);
+        }
+
+        self.steps += 1;
+        self.hits[ip as usize] += 1;
+        if accelerated {
+            self.accelerated[ip as usize] += 1;
+        } else if next <= ip {
+            *self.back_jumps.entry((ip, next)).or_insert(0) += 1;
+        }
+    }
+}
+
+// tracing writes one line per executed instruction, the profile counts them; both are off
+// by default, so that the interpreter only pays for them when they're needed
+impl<const N: usize, W: Word> Machine<N, W> {
+    pub fn trace_to<T: Write + 'static>(&mut self, writer: T) {
+        self.tracer = Some(Tracer {
+            writer: Box::new(writer),
+            error: None,
+        });
+    }
+
+    // stops tracing, returning the first error met while writing the trace
+    pub fn stop_trace(&mut self) -> io::Result<()> {
+        match self.tracer.take() {
+            Some(Tracer { error: Some(e), .. }) => Err(e),
+            Some(mut tracer) => tracer.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(Profile::default());
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    pub(super) fn observing(&self) -> bool {
+        self.tracer.is_some() || self.profile.is_some()
+    }
+
+    // `instruction` is None when the step ran an accelerated loop
+    pub(super) fn observe(
+        &mut self,
+        ip: i32,
+        instruction: Option<OpCode>,
+        before: &Registers<N, W>,
+    ) {
+        let next = self.curr_ip();
+        let len = self.program.len();
+
+        if let Some(profile) = &mut self.profile {
+            profile.record(ip, next, instruction.is_none(), len);
+        }
+
+        if let Some(tracer) = &mut self.tracer {
+            if tracer.error.is_some() {
+                return;
+            }
+            let op = match instruction {
+                Some(op) => op.to_string(),
+                None => String::from("<accelerated>"),
+            };
+            let res = writeln!(
+                tracer.writer,
+                "{:>4} {:<16} {:?} -> {:?}",
+                ip,
+                op,
+                before.all(),
+                self.registers.all()
+            );
+            if let Err(e) = res {
+                tracer.error = Some(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const PROGRAM: [&str; 6] = [
+        "#ip 0",
+        "seti 0 0 1",
+        "addi 1 1 1",
+        "gtri 1 1 2",
+        "addr 0 2 0",
+        "seti 0 0 0",
+    ];
+
+    #[test]
+    fn test_trace() {
+        let mut machine = Machine::<3>::parse(&PROGRAM).unwrap();
+        let out = Shared::default();
+        machine.trace_to(out.clone());
+        machine.run();
+        assert!(machine.stop_trace().is_ok());
+
+        let trace = String::from_utf8(out.0.borrow().clone()).unwrap();
+        let trace: Vec<&str> = trace.lines().collect();
+        assert_eq!(8, trace.len());
+        assert_eq!("   0 seti 0 0 1       [0, 0, 0] -> [1, 0, 0]", trace[0]);
+        assert_eq!("   4 seti 0 0 0       [4, 1, 0] -> [1, 1, 0]", trace[4]);
+        assert_eq!("   3 addr 0 2 0       [3, 2, 1] -> [5, 2, 1]", trace[7]);
+    }
+
+    #[test]
+    fn test_profile() {
+        let mut machine = Machine::<3>::parse(&PROGRAM).unwrap();
+        machine.enable_profiling();
+        machine.run();
+
+        let profile = machine.profile().unwrap();
+        assert_eq!(8, profile.steps());
+        assert_eq!(1, profile.hits(0));
+        assert_eq!(2, profile.hits(3));
+        assert_eq!(
+            vec![HotLoop {
+                start: 1,
+                end: 4,
+                iterations: 1,
+                executed: 7
+            }],
+            profile.hot_loops()
+        );
+        let report = profile.report(machine.program());
+        assert!(report
+            .lines()
+            .any(|l| l.starts_with("   1  addi 1 1 1") && l.ends_with(" 2     25.00%")));
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::ops::{BitAnd, BitOr, Div, Rem};
+use std::str::FromStr;
+
+// what additions and multiplications do when their result doesn't fit in a register
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Overflow {
+    Wrap,
+    Saturate,
+    Trap,
+}
+
+// the type of a register, implemented for the primitive integers wide enough for ElfCode
+// and for arbitrary precision ones, which never overflow
+pub trait Word:
+    Clone
+    + Ord
+    + Hash
+    + Debug
+    + Display
+    + FromStr
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + 'static
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    // None for unbounded words
+    fn min_value() -> Option<Self>;
+    fn max_value() -> Option<Self>;
+
+    // immediates are i32, negative ones wrap around for unsigned words
+    fn from_i32(v: i32) -> Self;
+    // clamped to the i32 range, which is enough for an instruction pointer
+    fn to_i32(self) -> i32;
+    // clamped to the u64 range, which is enough for a step count
+    fn to_u64(self) -> u64;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_div(self, other: Self) -> Option<Self>;
+    fn checked_rem(self, other: Self) -> Option<Self>;
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_mul(self, other: Self) -> Self;
+    fn saturating_add(self, other: Self) -> Self;
+    fn saturating_mul(self, other: Self) -> Self;
+
+    // None only when trapping
+    fn add_with(self, other: Self, overflow: Overflow) -> Option<Self> {
+        match overflow {
+            Overflow::Wrap => Some(self.wrapping_add(other)),
+            Overflow::Saturate => Some(self.saturating_add(other)),
+            Overflow::Trap => self.checked_add(other),
+        }
+    }
+
+    fn mul_with(self, other: Self, overflow: Overflow) -> Option<Self> {
+        match overflow {
+            Overflow::Wrap => Some(self.wrapping_mul(other)),
+            Overflow::Saturate => Some(self.saturating_mul(other)),
+            Overflow::Trap => self.checked_mul(other),
+        }
+    }
+}
+
+macro_rules! word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                fn zero() -> $t {
+                    0
+                }
+
+                fn one() -> $t {
+                    1
+                }
+
+                fn min_value() -> Option<$t> {
+                    Some(<$t>::MIN)
+                }
+
+                fn max_value() -> Option<$t> {
+                    Some(<$t>::MAX)
+                }
+
+                fn from_i32(v: i32) -> $t {
+                    v as $t
+                }
+
+                fn to_i32(self) -> i32 {
+                    if self > i32::MAX as $t {
+                        i32::MAX
+                    } else if (self as i128) < i32::MIN as i128 {
+                        i32::MIN
+                    } else {
+                        self as i32
+                    }
+                }
+
+                fn to_u64(self) -> u64 {
+                    if (self as i128) < 0 {
+                        0
+                    } else if self as u128 > u64::MAX as u128 {
+                        u64::MAX
+                    } else {
+                        self as u64
+                    }
+                }
+
+                fn checked_add(self, other: $t) -> Option<$t> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_sub(self, other: $t) -> Option<$t> {
+                    <$t>::checked_sub(self, other)
+                }
+
+                fn checked_mul(self, other: $t) -> Option<$t> {
+                    <$t>::checked_mul(self, other)
+                }
+
+                fn checked_div(self, other: $t) -> Option<$t> {
+                    <$t>::checked_div(self, other)
+                }
+
+                fn checked_rem(self, other: $t) -> Option<$t> {
+                    <$t>::checked_rem(self, other)
+                }
+
+                fn wrapping_add(self, other: $t) -> $t {
+                    <$t>::wrapping_add(self, other)
+                }
+
+                fn wrapping_mul(self, other: $t) -> $t {
+                    <$t>::wrapping_mul(self, other)
+                }
+
+                fn saturating_add(self, other: $t) -> $t {
+                    <$t>::saturating_add(self, other)
+                }
+
+                fn saturating_mul(self, other: $t) -> $t {
+                    <$t>::saturating_mul(self, other)
+                }
+            }
+        )*
+    };
+}
+
+word!(i32, i64, i128, u32, u64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(Some(i32::MIN), i32::MAX.add_with(1, Overflow::Wrap));
+        assert_eq!(Some(i32::MAX), i32::MAX.add_with(1, Overflow::Saturate));
+        assert_eq!(None, i32::MAX.add_with(1, Overflow::Trap));
+        assert_eq!(Some(0), 1u64.add_with(u64::MAX, Overflow::Wrap));
+        assert_eq!(Some(u64::MAX), u64::MAX.mul_with(2, Overflow::Saturate));
+        assert_eq!(
+            Some(1 << 40),
+            (1i64 << 20).mul_with(1 << 20, Overflow::Trap)
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(u64::MAX, u64::from_i32(-1));
+        assert_eq!(-1, i64::from_i32(-1));
+        assert_eq!(i32::MAX, u64::MAX.to_i32());
+        assert_eq!(i32::MIN, i64::MIN.to_i32());
+        assert_eq!(42, 42u32.to_i32());
+        assert_eq!(0, (-1i64).to_u64());
+        assert_eq!(u64::MAX, i128::MAX.to_u64());
+    }
+}
@@ -0,0 +1,170 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use regex::{Captures, Regex};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub expected: String,
+}
+
+impl ParseError {
+    pub fn new(column: usize, text: &str, expected: &str) -> ParseError {
+        ParseError {
+            line: 0,
+            column,
+            text: String::from(text),
+            expected: String::from(expected),
+        }
+    }
+
+    // about the input as a whole, when it lacks something rather than having a bad line
+    pub fn whole(expected: &str) -> ParseError {
+        ParseError::new(0, "", expected)
+    }
+
+    pub fn no_match(text: &str, re: &Regex) -> ParseError {
+        ParseError::new(1, text, re.as_str())
+    }
+
+    pub fn at_line(self, line: usize) -> ParseError {
+        ParseError { line, ..self }
+    }
+
+    pub fn at_column(self, column: usize) -> ParseError {
+        ParseError { column, ..self }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 && self.column == 0 {
+            return write!(f, "expected {}", self.expected);
+        }
+        write!(
+            f,
+            "line {}, column {}: expected {}, found \"{}\"",
+            self.line, self.column, self.expected, self.text
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseErrors(pub Vec<ParseError>);
+
+impl From<ParseError> for ParseErrors {
+    fn from(e: ParseError) -> ParseErrors {
+        ParseErrors(vec![e])
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseErrors {}
+
+pub trait FromLine: Sized {
+    fn from_line(line: &str) -> Result<Self, ParseError>;
+}
+
+pub fn parse_lines<T: FromLine>(input: &[&str]) -> Result<Vec<T>, ParseErrors> {
+    parse_lines_with(input, T::from_line)
+}
+
+pub fn parse_lines_with<T, F>(input: &[&str], f: F) -> Result<Vec<T>, ParseErrors>
+where
+    F: Fn(&str) -> Result<T, ParseError>,
+{
+    let mut res = Vec::with_capacity(input.len());
+    let mut errors = Vec::new();
+
+    for (i, line) in input.iter().enumerate() {
+        match f(line) {
+            Ok(v) => res.push(v),
+            Err(e) => errors.push(e.at_line(i + 1)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(res)
+    } else {
+        Err(ParseErrors(errors))
+    }
+}
+
+pub fn parse_value<F: FromStr>(text: &str, expected: &str) -> Result<F, ParseError> {
+    text.parse().map_err(|_| ParseError::new(1, text, expected))
+}
+
+pub fn parse_capture<F: FromStr>(cap: &Captures, idx: usize, name: &str) -> Result<F, ParseError> {
+    let ma = cap
+        .get(idx)
+        .ok_or_else(|| ParseError::new(1, &cap[0], name))?;
+    ma.as_str()
+        .parse::<F>()
+        .map_err(|_| ParseError::new(ma.start() + 1, ma.as_str(), name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Pair(u8, u8);
+
+    impl FromLine for Pair {
+        fn from_line(line: &str) -> Result<Pair, ParseError> {
+            let re = Regex::new(r"^(\d+),(\d+)$").unwrap();
+            let cap = re
+                .captures(line)
+                .ok_or_else(|| ParseError::no_match(line, &re))?;
+            Ok(Pair(
+                parse_capture(&cap, 1, "left")?,
+                parse_capture(&cap, 2, "right")?,
+            ))
+        }
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let pairs: Vec<Pair> = parse_lines(&["1,2", "3,4"]).unwrap();
+        assert_eq!((1, 2), (pairs[0].0, pairs[0].1));
+        assert_eq!((3, 4), (pairs[1].0, pairs[1].1));
+    }
+
+    #[test]
+    fn test_all_errors() {
+        let errors = parse_lines::<Pair>(&["1,2", "x", "3,400"]).err().unwrap();
+        assert_eq!(
+            ParseErrors(vec![
+                ParseError {
+                    line: 2,
+                    column: 1,
+                    text: String::from("x"),
+                    expected: String::from(r"^(\d+),(\d+)$"),
+                },
+                ParseError {
+                    line: 3,
+                    column: 3,
+                    text: String::from("400"),
+                    expected: String::from("right"),
+                },
+            ]),
+            errors
+        );
+    }
+}
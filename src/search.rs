@@ -0,0 +1,241 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+// a graph given by the moves out of each state; whenever two paths are equally good, the
+// one whose moves come first in the order `neighbours` returns them wins, which is how a
+// puzzle asks for reading order or any other tie-breaking rule
+pub trait Space {
+    type State: Clone + Eq + Hash;
+
+    // the states one move away, with the cost of that move
+    fn neighbours(&self, state: &Self::State) -> Vec<(Self::State, u32)>;
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Path<T> {
+    pub cost: u32,
+    // from the start to the goal, both included
+    pub states: Vec<T>,
+}
+
+fn unwind<T: Clone + Eq + Hash>(parents: &HashMap<T, T>, goal: &T) -> Vec<T> {
+    let mut res = vec![goal.clone()];
+    while let Some(parent) = parents.get(res.last().unwrap()) {
+        res.push(parent.clone());
+    }
+    res.reverse();
+    res
+}
+
+// everything reachable from a start, with the fewest moves to get there
+#[derive(Clone, Debug)]
+pub struct Reached<T: Eq + Hash> {
+    start: T,
+    moves: HashMap<T, u32>,
+    parents: HashMap<T, T>,
+    order: Vec<T>,
+}
+
+impl<T: Clone + Eq + Hash> Reached<T> {
+    pub fn moves(&self, state: &T) -> Option<u32> {
+        self.moves.get(state).copied()
+    }
+
+    // in the order they were reached, so by increasing number of moves
+    pub fn iter(&self) -> impl Iterator<Item = (&T, u32)> {
+        self.order.iter().map(move |s| (s, self.moves[s]))
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    // the first of the shortest paths in the order of the moves
+    pub fn path_to(&self, goal: &T) -> Option<Path<T>> {
+        let cost = self.moves(goal)?;
+        let states = unwind(&self.parents, goal);
+        debug_assert!(states[0] == self.start);
+        Some(Path { cost, states })
+    }
+}
+
+// breadth-first, counting moves and ignoring their cost
+pub fn bfs<S: Space>(space: &S, start: S::State) -> Reached<S::State> {
+    bfs_until(space, start, |_| false)
+}
+
+// stops once everything as close as the nearest goal is reached
+pub fn bfs_until<S, G>(space: &S, start: S::State, is_goal: G) -> Reached<S::State>
+where
+    S: Space,
+    G: Fn(&S::State) -> bool,
+{
+    let mut found = if is_goal(&start) { Some(0) } else { None };
+    let mut moves = HashMap::new();
+    let mut parents = HashMap::new();
+    let mut order = vec![start.clone()];
+    let mut queue = VecDeque::new();
+    moves.insert(start.clone(), 0);
+    queue.push_back(start.clone());
+
+    while let Some(state) = queue.pop_front() {
+        let n = moves[&state] + 1;
+        if found.is_some_and(|f| n > f) {
+            break;
+        }
+        for (next, _) in space.neighbours(&state) {
+            if moves.contains_key(&next) {
+                continue;
+            }
+            if found.is_none() && is_goal(&next) {
+                found = Some(n);
+            }
+            moves.insert(next.clone(), n);
+            parents.insert(next.clone(), state.clone());
+            order.push(next.clone());
+            queue.push_back(next);
+        }
+    }
+
+    Reached {
+        start,
+        moves,
+        parents,
+        order,
+    }
+}
+
+pub fn dijkstra<S, G>(space: &S, start: S::State, is_goal: G) -> Option<Path<S::State>>
+where
+    S: Space,
+    G: Fn(&S::State) -> bool,
+{
+    astar(space, start, is_goal, |_| 0)
+}
+
+// the heuristic must never overestimate the cost left to a goal, nor decrease by more than
+// the cost of a move, for the path found to be the cheapest
+pub fn astar<S, G, H>(
+    space: &S,
+    start: S::State,
+    is_goal: G,
+    heuristic: H,
+) -> Option<Path<S::State>>
+where
+    S: Space,
+    G: Fn(&S::State) -> bool,
+    H: Fn(&S::State) -> u32,
+{
+    let mut costs = HashMap::new();
+    let mut parents = HashMap::new();
+    let mut done = HashSet::new();
+    // the queue refers to the states by the order they were queued in, which also breaks
+    // ties between equal estimates
+    let mut queued = Vec::new();
+    let mut queue = BinaryHeap::new();
+
+    costs.insert(start.clone(), 0);
+    queue.push(Reverse((heuristic(&start), 0)));
+    queued.push((start, 0));
+
+    while let Some(Reverse((_, i))) = queue.pop() {
+        let (state, cost) = queued[i].clone();
+        if cost > costs[&state] || !done.insert(state.clone()) {
+            continue;
+        }
+        if is_goal(&state) {
+            let states = unwind(&parents, &state);
+            return Some(Path { cost, states });
+        }
+
+        for (next, step) in space.neighbours(&state) {
+            let cost = cost + step;
+            if done.contains(&next) || costs.get(&next).is_some_and(|&c| c <= cost) {
+                continue;
+            }
+            costs.insert(next.clone(), cost);
+            parents.insert(next.clone(), state.clone());
+            queue.push(Reverse((cost + heuristic(&next), queued.len())));
+            queued.push((next, cost));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // the integers, moving by +1 for 1 or by *2 for 3
+    struct Numbers;
+
+    impl Space for Numbers {
+        type State = u32;
+
+        fn neighbours(&self, state: &u32) -> Vec<(u32, u32)> {
+            vec![(state + 1, 1), (state * 2, 3)]
+        }
+    }
+
+    // a 3x3 grid of 0..9, moving right and down
+    struct Square;
+
+    impl Space for Square {
+        type State = u32;
+
+        fn neighbours(&self, state: &u32) -> Vec<(u32, u32)> {
+            let mut res = Vec::new();
+            if state % 3 < 2 {
+                res.push((state + 1, 1));
+            }
+            if *state < 6 {
+                res.push((state + 3, 1));
+            }
+            res
+        }
+    }
+
+    #[test]
+    fn test_bfs() {
+        let reached = bfs(&Square, 0);
+        assert_eq!(9, reached.len());
+        assert_eq!(Some(4), reached.moves(&8));
+        let order: Vec<u32> = reached.iter().map(|(s, _)| *s).collect();
+        assert_eq!(vec![0, 1, 3, 2, 4, 6, 5, 7, 8], order);
+        // right before down
+        assert_eq!(vec![0, 1, 2, 5, 8], reached.path_to(&8).unwrap().states);
+        assert_eq!(None, reached.path_to(&9));
+
+        let reached = bfs_until(&Square, 0, |&s| s == 4 || s == 6);
+        let order: Vec<u32> = reached.iter().map(|(s, _)| *s).collect();
+        assert_eq!(vec![0, 1, 3, 2, 4, 6], order);
+        assert_eq!(1, bfs_until(&Square, 0, |&s| s == 0).len());
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let path = dijkstra(&Numbers, 1, |&n| n == 20).unwrap();
+        // doubling only pays off from 5 on
+        assert_eq!(10, path.cost);
+        assert_eq!(vec![1, 2, 3, 4, 5, 10, 20], path.states);
+        assert_eq!(None, dijkstra(&Square, 3, |&n| n == 2));
+
+        let path = dijkstra(&Square, 0, |&n| n == 4).unwrap();
+        assert_eq!(vec![0, 1, 4], path.states);
+    }
+
+    #[test]
+    fn test_astar() {
+        let path = astar(&Numbers, 1, |&n| n == 20, |&n| (20 - n.min(20)) / 10).unwrap();
+        assert_eq!(10, path.cost);
+        assert_eq!(vec![1, 2, 3, 4, 5, 10, 20], path.states);
+
+        let path = astar(&Square, 0, |&n| n == 8, |&n| 4 - n / 3 - n % 3).unwrap();
+        assert_eq!(vec![0, 1, 2, 5, 8], path.states);
+    }
+}